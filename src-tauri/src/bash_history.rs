@@ -0,0 +1,258 @@
+use crate::jsonl_tail::{TailCursor, TailLine};
+use crate::models::{CommandLogEntry, CommandSource, CommandStatus};
+use chrono::{DateTime, Duration, Utc};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+/// A log line and a Bash call for the same command are merged when they are
+/// at most this far apart; hooks may log before or after the command runs
+const MERGE_WINDOW_SECONDS: i64 = 60;
+
+/// Name recorded as `user` for commands taken from session files
+fn local_user_name() -> String {
+    std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_default()
+}
+
+#[derive(Default)]
+struct FileCommands {
+    cursor: TailCursor,
+    entries: Vec<CommandLogEntry>,
+    /// tool_use id -> index into `entries` of calls still waiting for a result
+    pending: HashMap<String, usize>,
+}
+
+/// Bash tool calls extracted from session files, updated as the files grow
+#[derive(Default)]
+pub struct BashCommandIndex {
+    files: HashMap<PathBuf, FileCommands>,
+}
+
+impl BashCommandIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Where extraction from `file_path` stopped, to resume with `jsonl_tail::read_appended_lines`
+    pub fn cursor(&self, file_path: &Path) -> Option<&TailCursor> {
+        self.files.get(file_path).map(|file| &file.cursor)
+    }
+
+    /// Extract commands from newly read lines; `reset` drops what was extracted before
+    pub fn update_file(
+        &mut self,
+        file_path: &Path,
+        session_id: &str,
+        reset: bool,
+        cursor: TailCursor,
+        lines: &[TailLine],
+    ) {
+        let file = self.files.entry(file_path.to_path_buf()).or_default();
+        if reset {
+            *file = FileCommands::default();
+        }
+
+        for line in lines {
+            if let Ok(raw) = serde_json::from_str::<serde_json::Value>(&line.text) {
+                apply_line(&raw, session_id, file);
+            }
+        }
+        file.cursor = cursor;
+    }
+
+    /// Drop files that no longer exist
+    pub fn retain(&mut self, seen: &HashSet<PathBuf>) {
+        self.files.retain(|path, _| seen.contains(path));
+    }
+
+    pub fn clear(&mut self) {
+        self.files.clear();
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = &CommandLogEntry> {
+        self.files.values().flat_map(|file| file.entries.iter())
+    }
+}
+
+fn content_blocks(raw: &serde_json::Value) -> &[serde_json::Value] {
+    raw.get("message")
+        .and_then(|m| m.get("content"))
+        .and_then(|c| c.as_array())
+        .map(Vec::as_slice)
+        .unwrap_or_default()
+}
+
+fn str_field(value: &serde_json::Value, key: &str) -> Option<String> {
+    value.get(key).and_then(|v| v.as_str()).map(str::to_string)
+}
+
+fn apply_line(raw: &serde_json::Value, session_id: &str, file: &mut FileCommands) {
+    match raw.get("type").and_then(|t| t.as_str()) {
+        Some("assistant") => {
+            for block in content_blocks(raw) {
+                if block.get("type").and_then(|t| t.as_str()) != Some("tool_use")
+                    || block.get("name").and_then(|n| n.as_str()) != Some("Bash")
+                {
+                    continue;
+                }
+                let input = block.get("input").unwrap_or(&serde_json::Value::Null);
+                let Some(command) = str_field(input, "command") else {
+                    continue;
+                };
+
+                let raw_timestamp = str_field(raw, "timestamp").unwrap_or_default();
+                file.entries.push(CommandLogEntry {
+                    timestamp: raw_timestamp.parse::<DateTime<Utc>>().ok(),
+                    raw_timestamp,
+                    user: local_user_name(),
+                    command,
+                    cwd: str_field(raw, "cwd"),
+                    source: CommandSource::Session,
+                    session_id: Some(session_id.to_string()),
+                    git_branch: str_field(raw, "gitBranch").filter(|b| !b.is_empty()),
+                    description: str_field(input, "description"),
+                    status: Some(CommandStatus::Pending),
+                    exit_code: None,
+                    duration_ms: None,
+                });
+                if let Some(id) = str_field(block, "id") {
+                    file.pending.insert(id, file.entries.len() - 1);
+                }
+            }
+        }
+        Some("user") => {
+            for block in content_blocks(raw) {
+                if block.get("type").and_then(|t| t.as_str()) != Some("tool_result") {
+                    continue;
+                }
+                let Some(index) =
+                    str_field(block, "tool_use_id").and_then(|id| file.pending.remove(&id))
+                else {
+                    continue;
+                };
+
+                let is_error = block
+                    .get("is_error")
+                    .and_then(|e| e.as_bool())
+                    .unwrap_or(false);
+                let interrupted = raw
+                    .get("toolUseResult")
+                    .and_then(|r| r.get("interrupted"))
+                    .and_then(|i| i.as_bool())
+                    .unwrap_or(false);
+                let (status, exit_code) =
+                    command_outcome(&tool_result_text(block), is_error, interrupted);
+
+                let finished =
+                    str_field(raw, "timestamp").and_then(|t| t.parse::<DateTime<Utc>>().ok());
+
+                let entry = &mut file.entries[index];
+                entry.status = Some(status);
+                entry.exit_code = exit_code;
+                entry.duration_ms =
+                    entry
+                        .timestamp
+                        .zip(finished)
+                        .and_then(|(started, finished)| {
+                            u64::try_from((finished - started).num_milliseconds()).ok()
+                        });
+            }
+        }
+        _ => {}
+    }
+}
+
+fn tool_result_text(block: &serde_json::Value) -> String {
+    match block.get("content") {
+        Some(serde_json::Value::String(text)) => text.clone(),
+        Some(serde_json::Value::Array(items)) => items
+            .iter()
+            .filter_map(|item| item.get("text").and_then(|t| t.as_str()))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        _ => String::new(),
+    }
+}
+
+/// Status and exit code of a Bash call from its tool_result
+///
+/// Failed calls report `Exit code N` in their output; successful calls exited with 0.
+pub fn command_outcome(
+    text: &str,
+    is_error: bool,
+    interrupted: bool,
+) -> (CommandStatus, Option<i32>) {
+    if interrupted || text.contains("[Request interrupted by user") {
+        return (CommandStatus::Interrupted, None);
+    }
+    if !is_error {
+        return (CommandStatus::Succeeded, Some(0));
+    }
+
+    let exit_code = text.lines().find_map(|line| {
+        line.trim()
+            .strip_prefix("Exit code ")
+            .and_then(|code| code.trim().parse::<i32>().ok())
+    });
+    (CommandStatus::Failed, exit_code)
+}
+
+/// Merge log-file entries with Bash calls from sessions into one timeline, newest first
+///
+/// Each log line is matched with the closest unmatched Bash call running the
+/// same command within `MERGE_WINDOW_SECONDS`; matched pairs become one entry
+/// that keeps the log's user and gains the session's details.
+pub fn merge_command_history(
+    log_entries: Vec<CommandLogEntry>,
+    session_entries: Vec<CommandLogEntry>,
+) -> Vec<CommandLogEntry> {
+    let mut by_command: HashMap<String, Vec<usize>> = HashMap::new();
+    for (index, entry) in session_entries.iter().enumerate() {
+        by_command
+            .entry(entry.command.trim().to_string())
+            .or_default()
+            .push(index);
+    }
+
+    let mut session_entries: Vec<Option<CommandLogEntry>> =
+        session_entries.into_iter().map(Some).collect();
+    let mut merged = Vec::new();
+    let window = Duration::seconds(MERGE_WINDOW_SECONDS);
+
+    for mut log_entry in log_entries {
+        let candidates = by_command.get(log_entry.command.trim());
+        let closest = log_entry.timestamp.and_then(|logged| {
+            candidates?
+                .iter()
+                .filter_map(|index| {
+                    let ran = session_entries[*index].as_ref()?.timestamp?;
+                    let distance = (ran - logged).abs();
+                    (distance <= window).then_some((distance, *index))
+                })
+                .min()
+        });
+
+        if let Some(session_entry) = closest.and_then(|(_, index)| session_entries[index].take()) {
+            log_entry.source = CommandSource::Both;
+            log_entry.cwd = log_entry.cwd.or(session_entry.cwd);
+            log_entry.session_id = session_entry.session_id;
+            log_entry.git_branch = session_entry.git_branch;
+            log_entry.description = session_entry.description;
+            log_entry.status = session_entry.status;
+            log_entry.exit_code = session_entry.exit_code;
+            log_entry.duration_ms = session_entry.duration_ms;
+        }
+        merged.push(log_entry);
+    }
+    merged.extend(session_entries.into_iter().flatten());
+
+    // Newest first; entries without a parsed time keep their order at the end
+    merged.sort_by(|a, b| match (a.timestamp, b.timestamp) {
+        (Some(a), Some(b)) => b.cmp(&a),
+        (Some(_), None) => std::cmp::Ordering::Less,
+        (None, Some(_)) => std::cmp::Ordering::Greater,
+        (None, None) => std::cmp::Ordering::Equal,
+    });
+    merged
+}
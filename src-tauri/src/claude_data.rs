@@ -1,14 +1,38 @@
+use crate::bash_history::{self, BashCommandIndex};
+use crate::command_analytics;
+use crate::conversation_tree::{self, TreeEntry};
+use crate::cost::CostReportBuilder;
+use crate::error::{ManagerError, ManagerResult, PathContext};
+use crate::file_store::{self, FileStore};
+use crate::file_watcher;
+use crate::hook_runner;
+use crate::jsonl_tail::{self, TailCursor, TailLine, TailRead};
+use crate::log_timestamp::parse_log_timestamp;
 use crate::models::*;
+use crate::path_policy::{self, PathPolicy};
+use crate::permission_suggestions::{self, ObservedToolCall};
+use crate::permissions::{PermissionPolicy, PermissionRule, ToolCall};
+use crate::query::{MessageFacts, Query, SessionTarget};
+use crate::redaction::{RedactionContext, Redactor};
+use crate::search_index::{self, IndexedMessage, SearchIndex};
+use crate::session_diagnostics;
+use crate::session_index::{RequestUsage, SessionIndex, SessionScan};
+use crate::settings_layers;
+use crate::settings_validation;
+use crate::transcript;
 use chrono::{DateTime, Utc};
 use dirs::home_dir;
 use notify::{Config, Event, RecommendedWatcher, RecursiveMode, Watcher};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::io::{BufRead, BufReader};
 use std::path::{Path, PathBuf};
-use std::sync::mpsc;
+use std::sync::{mpsc, Mutex};
 use tokio::sync::RwLock;
 
+/// Base64 images larger than this are replaced by a handle and loaded on demand
+const INLINE_IMAGE_MAX_BYTES: usize = 64 * 1024;
+
 /// Enum representing the markdown file directories
 #[derive(Debug, Clone, Copy)]
 enum MarkdownDirectory {
@@ -34,25 +58,46 @@ impl MarkdownDirectory {
     }
 }
 
+/// Parsed messages of a session file and where parsing stopped
+struct CachedMessages {
+    file_path: PathBuf,
+    size: u64,
+    modified: DateTime<Utc>,
+    cursor: TailCursor,
+    messages: Vec<ClaudeMessage>,
+}
+
 pub struct ClaudeDataManager {
     claude_dir: PathBuf,
     _sessions_cache: RwLock<HashMap<String, ClaudeSession>>,
-    messages_cache: RwLock<HashMap<String, Vec<ClaudeMessage>>>,
+    messages_cache: RwLock<HashMap<String, CachedMessages>>,
     file_timestamps: RwLock<HashMap<PathBuf, DateTime<Utc>>>,
+    session_index: RwLock<SessionIndex>,
+    search_index: RwLock<SearchIndex>,
+    bash_commands: RwLock<BashCommandIndex>,
+    /// Enterprise settings that override every other layer; None in tests
+    managed_settings_path: Option<PathBuf>,
+    /// Every write to a file under ~/.claude goes through here
+    file_store: FileStore,
     _watcher: Option<RecommendedWatcher>,
+    /// Raw watcher events, handed to the debouncer thread once notifications are started
+    watch_events: Mutex<Option<mpsc::Receiver<Event>>>,
 }
 
 impl ClaudeDataManager {
-    pub fn new() -> Result<Self, Box<dyn std::error::Error>> {
-        let home = home_dir().ok_or("Could not find home directory")?;
+    pub fn new() -> ManagerResult<Self> {
+        let home =
+            home_dir().ok_or_else(|| ManagerError::not_found("Could not find home directory"))?;
         let claude_dir = home.join(".claude");
 
         if !claude_dir.exists() {
-            return Err("~/.claude directory not found".into());
+            return Err(
+                ManagerError::not_found("~/.claude directory not found").with_path(&claude_dir)
+            );
         }
 
         // Create file watcher
-        let (tx, _rx) = mpsc::channel();
+        let (tx, rx) = mpsc::channel();
         let mut watcher = RecommendedWatcher::new(
             move |res: Result<Event, notify::Error>| {
                 match res {
@@ -74,14 +119,20 @@ impl ClaudeDataManager {
             _sessions_cache: RwLock::new(HashMap::new()),
             messages_cache: RwLock::new(HashMap::new()),
             file_timestamps: RwLock::new(HashMap::new()),
+            session_index: RwLock::new(SessionIndex::in_memory()),
+            search_index: RwLock::new(SearchIndex::new()),
+            bash_commands: RwLock::new(BashCommandIndex::new()),
+            managed_settings_path: Some(settings_layers::default_managed_settings_path()),
+            file_store: FileStore::without_backups(),
             _watcher: Some(watcher),
+            watch_events: Mutex::new(Some(rx)),
         })
     }
 
     #[cfg(test)]
-    pub fn new_with_dir(claude_dir: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+    pub fn new_with_dir(claude_dir: &Path) -> ManagerResult<Self> {
         if !claude_dir.exists() {
-            return Err("Claude directory not found".into());
+            return Err(ManagerError::not_found("Claude directory not found").with_path(claude_dir));
         }
 
         Ok(Self {
@@ -89,11 +140,54 @@ impl ClaudeDataManager {
             _sessions_cache: RwLock::new(HashMap::new()),
             messages_cache: RwLock::new(HashMap::new()),
             file_timestamps: RwLock::new(HashMap::new()),
+            session_index: RwLock::new(SessionIndex::in_memory()),
+            search_index: RwLock::new(SearchIndex::new()),
+            bash_commands: RwLock::new(BashCommandIndex::new()),
+            managed_settings_path: None,
+            file_store: FileStore::without_backups(),
             _watcher: None, // No watcher in test mode
+            watch_events: Mutex::new(None),
         })
     }
 
-    pub async fn get_all_sessions(&self) -> Result<Vec<ClaudeSession>, Box<dyn std::error::Error>> {
+    /// Read enterprise managed settings from `path` instead of the platform default
+    #[cfg(test)]
+    pub fn with_managed_settings(mut self, path: PathBuf) -> Self {
+        self.managed_settings_path = Some(path);
+        self
+    }
+
+    /// Persist session summaries at `index_path` so restarts only re-parse changed files
+    pub fn with_session_index(mut self, index_path: PathBuf) -> Self {
+        self.session_index = RwLock::new(SessionIndex::load(index_path));
+        self
+    }
+
+    /// Keep up to `max_backups` previous versions of each edited file in `backup_dir`
+    pub fn with_backups(mut self, backup_dir: PathBuf, max_backups: usize) -> Self {
+        self.file_store = FileStore::with_backups(backup_dir, max_backups);
+        self
+    }
+
+    /// Start forwarding debounced file changes under ~/.claude to `emit`
+    ///
+    /// Returns false if notifications were already started or there is no watcher.
+    pub fn start_change_notifications<F>(&self, emit: F) -> bool
+    where
+        F: FnMut(FileChangeEvent) + Send + 'static,
+    {
+        let Some(events) = self.watch_events.lock().ok().and_then(|mut rx| rx.take()) else {
+            return false;
+        };
+
+        let claude_dir = self.claude_dir.clone();
+        std::thread::Builder::new()
+            .name("claude-file-watcher".to_string())
+            .spawn(move || file_watcher::run_debouncer(claude_dir, events, emit))
+            .is_ok()
+    }
+
+    pub async fn get_all_sessions(&self) -> ManagerResult<Vec<ClaudeSession>> {
         let projects_dir = self.claude_dir.join("projects");
         let mut sessions = Vec::new();
         let mut project_path_map: HashMap<String, String> = HashMap::new();
@@ -103,8 +197,8 @@ impl ClaudeDataManager {
         }
 
         // First, collect all sessions to build a complete mapping
-        for entry in fs::read_dir(&projects_dir)? {
-            let entry = entry?;
+        for entry in fs::read_dir(&projects_dir).at_path(&projects_dir)? {
+            let entry = entry.at_path(&projects_dir)?;
             let project_path = entry.path();
             if project_path.is_dir() {
                 let project_name = project_path
@@ -135,8 +229,9 @@ impl ClaudeDataManager {
         }
 
         // Now process all sessions with the mapping
-        for entry in fs::read_dir(&projects_dir)? {
-            let entry = entry?;
+        let mut seen_files = HashSet::new();
+        for entry in fs::read_dir(&projects_dir).at_path(&projects_dir)? {
+            let entry = entry.at_path(&projects_dir)?;
             let project_path = entry.path();
             if project_path.is_dir() {
                 let project_name = project_path
@@ -145,8 +240,8 @@ impl ClaudeDataManager {
                     .unwrap_or("")
                     .to_string();
 
-                for session_file in fs::read_dir(&project_path)? {
-                    let session_file = session_file?;
+                for session_file in fs::read_dir(&project_path).at_path(&project_path)? {
+                    let session_file = session_file.at_path(&project_path)?;
                     let file_path = session_file.path();
 
                     if file_path.extension().and_then(|e| e.to_str()) == Some("jsonl") {
@@ -167,136 +262,641 @@ impl ClaudeDataManager {
                         };
 
                         let session = self
-                            .parse_session_file(&file_path, &session_id, &effective_project_name)
+                            .load_session_summary(&file_path, &session_id, &effective_project_name)
                             .await?;
                         sessions.push(session);
+                        seen_files.insert(file_path);
                     }
                 }
             }
         }
 
+        {
+            let mut index = self.session_index.write().await;
+            index.retain(&seen_files);
+            if let Err(e) = index.save() {
+                eprintln!("Failed to save session index: {e}");
+            }
+        }
+        self.search_index.write().await.retain(&seen_files);
+
         sessions.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
         Ok(sessions)
     }
 
+    /// Return the indexed summary when the file is unchanged, otherwise re-parse it
+    async fn load_session_summary(
+        &self,
+        file_path: &Path,
+        session_id: &str,
+        project_path: &str,
+    ) -> ManagerResult<ClaudeSession> {
+        let metadata = fs::metadata(file_path).at_path(file_path)?;
+        let size = metadata.len();
+        let modified = DateTime::<Utc>::from(metadata.modified().at_path(file_path)?);
+
+        let (cached, previous_scan) = {
+            let index = self.session_index.read().await;
+            (
+                index.get(file_path, size, modified).cloned(),
+                index.get_scan(file_path).cloned(),
+            )
+        };
+
+        if let Some(mut session) = cached {
+            // IDE lock files change independently of the session file
+            session.ide_info = self.find_ide_info_for_project(&session.project_path).await;
+            return Ok(session);
+        }
+
+        let previous_cursor = previous_scan.as_ref().map(|scan| scan.cursor.clone());
+        let tail = jsonl_tail::read_appended_lines(file_path, previous_cursor.as_ref())
+            .at_path(file_path)?;
+        let (session, scan) = self
+            .parse_session_file(file_path, session_id, project_path, previous_scan, &tail)
+            .await?;
+        self.feed_search_index(file_path, &session, previous_cursor.as_ref(), &tail)
+            .await?;
+        self.session_index
+            .write()
+            .await
+            .insert(file_path, size, modified, session.clone(), scan);
+
+        Ok(session)
+    }
+
+    /// Index lines the summary scan just read, if the search index stopped where they start
+    ///
+    /// This never reads the file, so scanning summaries stays as cheap as the
+    /// session index makes it; files the index is behind on are read by
+    /// `catch_up_search_index` when a search needs them.
+    async fn feed_search_index(
+        &self,
+        file_path: &Path,
+        session: &ClaudeSession,
+        from: Option<&TailCursor>,
+        read: &TailRead,
+    ) -> ManagerResult<()> {
+        if !read.reset && self.search_index.read().await.cursor(file_path) != from {
+            return Ok(());
+        }
+        let messages = self.indexable_messages(&session.session_id, read)?;
+        let mut index = self.search_index.write().await;
+        if read.reset || index.cursor(file_path) == from {
+            index.update_file(
+                file_path,
+                &session.project_path,
+                read.reset,
+                read.cursor.clone(),
+                &messages,
+            );
+        }
+        Ok(())
+    }
+
+    /// Bring the full-text index level with every indexed session file
+    ///
+    /// Each file is read from where the index stopped, outside the index lock.
+    /// The index starts empty on every launch, so the first search reads each
+    /// session once; later ones only read what was appended.
+    async fn catch_up_search_index(&self) -> ManagerResult<()> {
+        let files: Vec<(PathBuf, ClaudeSession, TailCursor)> = self
+            .session_index
+            .read()
+            .await
+            .files()
+            .map(|(path, session, scan)| (path.to_path_buf(), session.clone(), scan.cursor.clone()))
+            .collect();
+
+        for (file_path, session, cursor) in files {
+            let from = self.search_index.read().await.cursor(&file_path).cloned();
+            if from.as_ref() == Some(&cursor) {
+                continue;
+            }
+            // A file removed since the scan is pruned by the next one
+            let Ok(read) = jsonl_tail::read_appended_lines(&file_path, from.as_ref()) else {
+                continue;
+            };
+            let messages = self.indexable_messages(&session.session_id, &read)?;
+
+            let mut index = self.search_index.write().await;
+            // Skip the update if a concurrent search already moved the index on
+            if index.cursor(&file_path) == from.as_ref() {
+                index.update_file(
+                    &file_path,
+                    &session.project_path,
+                    read.reset,
+                    read.cursor,
+                    &messages,
+                );
+            }
+        }
+        Ok(())
+    }
+
+    fn indexable_messages(
+        &self,
+        session_id: &str,
+        read: &TailRead,
+    ) -> ManagerResult<Vec<IndexedMessage>> {
+        let mut messages = Vec::new();
+        for line in &read.lines {
+            if let Ok(Some(raw)) = session_diagnostics::parse_line(line) {
+                if let Some(message) = self.parse_claude_message(&raw, session_id)? {
+                    messages.push(IndexedMessage {
+                        offset: line.offset,
+                        message,
+                    });
+                }
+            }
+        }
+        Ok(messages)
+    }
+
+    /// Searchable text of the message on the line at `offset`, read back for snippets
+    fn message_text_at(&self, file_path: &Path, offset: u64, session_id: &str) -> Option<String> {
+        let line = jsonl_tail::read_line_at(file_path, offset).ok()?;
+        let raw = serde_json::from_str::<serde_json::Value>(&line).ok()?;
+        let message = self.parse_claude_message(&raw, session_id).ok()??;
+        Some(search_index::searchable_text(&message))
+    }
+
+    /// Fold the lines `tail` read since `previous` into the session summary.
+    /// Starts over when there is no previous scan or the file was rewritten.
     async fn parse_session_file(
         &self,
         file_path: &Path,
         session_id: &str,
         project_path: &str,
-    ) -> Result<ClaudeSession, Box<dyn std::error::Error>> {
+        previous: Option<SessionScan>,
+        tail: &TailRead,
+    ) -> ManagerResult<(ClaudeSession, SessionScan)> {
         // Get file modification time first
         let file_modified_time = self.get_file_modified_time(file_path).await?;
-        let file = fs::File::open(file_path)?;
-        let reader = BufReader::new(file);
 
-        let mut message_count = 0;
-        let mut first_timestamp: Option<DateTime<Utc>> = None;
-        let mut git_branch: Option<String> = None;
-        let mut latest_content_preview: Option<String> = None;
-        let mut latest_timestamp: Option<DateTime<Utc>> = None;
-        let mut has_incomplete_sequence = false;
-        let mut actual_project_path: Option<String> = None;
+        let mut scan = match previous {
+            Some(previous) if !tail.reset => previous,
+            _ => SessionScan::default(),
+        };
+        scan.cursor = tail.cursor.clone();
+
+        for line in &tail.lines {
+            let message = match session_diagnostics::parse_line(line) {
+                Ok(Some(message)) => message,
+                Ok(None) => continue,
+                Err(_) => {
+                    scan.corrupted_lines += 1;
+                    continue;
+                }
+            };
+            if session_diagnostics::check_entry_type(line, &message).is_some() {
+                scan.unrecognized_lines += 1;
+            }
+            scan.message_count += 1;
 
-        for line in reader.lines() {
-            let line = line?;
-            if let Ok(message) = serde_json::from_str::<serde_json::Value>(&line) {
-                message_count += 1;
-
-                if let Some(timestamp_str) = message.get("timestamp").and_then(|t| t.as_str()) {
-                    if let Ok(timestamp) = timestamp_str.parse::<DateTime<Utc>>() {
-                        // Keep the first timestamp we encounter
-                        if first_timestamp.is_none() {
-                            first_timestamp = Some(timestamp);
-                        }
+            if let Some(timestamp_str) = message.get("timestamp").and_then(|t| t.as_str()) {
+                if let Ok(timestamp) = timestamp_str.parse::<DateTime<Utc>>() {
+                    // Keep the first timestamp we encounter
+                    if scan.first_timestamp.is_none() {
+                        scan.first_timestamp = Some(timestamp);
+                    }
 
-                        // Extract latest content for preview
-                        if latest_timestamp.is_none() || timestamp > latest_timestamp.unwrap() {
-                            latest_timestamp = Some(timestamp);
-                            latest_content_preview = self.extract_content_preview(&message);
-                        }
+                    // Extract latest content for preview
+                    if scan
+                        .latest_timestamp
+                        .is_none_or(|latest| timestamp > latest)
+                    {
+                        scan.latest_timestamp = Some(timestamp);
+                        scan.latest_content_preview = self.extract_content_preview(&message);
                     }
                 }
+            }
 
-                if git_branch.is_none() {
-                    if let Some(branch) = message.get("gitBranch").and_then(|b| b.as_str()) {
-                        if !branch.is_empty() {
-                            git_branch = Some(branch.to_string());
-                        }
+            if scan.git_branch.is_none() {
+                if let Some(branch) = message.get("gitBranch").and_then(|b| b.as_str()) {
+                    if !branch.is_empty() {
+                        scan.git_branch = Some(branch.to_string());
                     }
                 }
+            }
 
-                // Get the actual project path from cwd
-                if actual_project_path.is_none() {
-                    if let Some(cwd) = message.get("cwd").and_then(|c| c.as_str()) {
-                        if !cwd.is_empty() {
-                            actual_project_path = Some(cwd.to_string());
-                        }
+            // Get the actual project path from cwd
+            if scan.actual_project_path.is_none() {
+                if let Some(cwd) = message.get("cwd").and_then(|c| c.as_str()) {
+                    if !cwd.is_empty() {
+                        scan.actual_project_path = Some(cwd.to_string());
                     }
                 }
+            }
 
-                // Check for incomplete sequences (assistant messages without stop_reason)
-                if message.get("type").and_then(|t| t.as_str()) == Some("assistant") {
-                    let has_stop_reason = message
-                        .get("message")
-                        .and_then(|m| m.get("stop_reason"))
-                        .is_some();
+            // Check for incomplete sequences (assistant messages without stop_reason)
+            if message.get("type").and_then(|t| t.as_str()) == Some("assistant") {
+                let has_stop_reason = message
+                    .get("message")
+                    .and_then(|m| m.get("stop_reason"))
+                    .is_some();
 
-                    if !has_stop_reason {
-                        has_incomplete_sequence = true;
-                    }
+                if !has_stop_reason {
+                    scan.has_incomplete_sequence = true;
+                }
+
+                if let Some(usage) = message
+                    .get("message")
+                    .and_then(|m| m.get("usage"))
+                    .map(Self::parse_token_usage)
+                {
+                    let request_key = message
+                        .get("requestId")
+                        .or_else(|| message.get("message").and_then(|m| m.get("id")))
+                        .or_else(|| message.get("uuid"))
+                        .and_then(|k| k.as_str())
+                        .unwrap_or("")
+                        .to_string();
+                    let timestamp = message
+                        .get("timestamp")
+                        .and_then(|t| t.as_str())
+                        .and_then(|t| t.parse().ok());
+                    let model = message
+                        .get("message")
+                        .and_then(|m| m.get("model"))
+                        .and_then(|m| m.as_str())
+                        .map(str::to_string);
+                    scan.usage_by_request.insert(
+                        request_key,
+                        RequestUsage {
+                            timestamp,
+                            model,
+                            usage,
+                        },
+                    );
                 }
             }
         }
 
+        let mut token_usage = TokenUsage::default();
+        for request in scan.usage_by_request.values() {
+            token_usage.add(&request.usage);
+        }
+
         // Use actual project path from cwd if available, otherwise fall back to encoded path
-        let display_project_path = actual_project_path.unwrap_or_else(|| project_path.to_string());
+        let display_project_path = scan
+            .actual_project_path
+            .clone()
+            .unwrap_or_else(|| project_path.to_string());
 
         let ide_info = self.find_ide_info_for_project(&display_project_path).await;
 
-        Ok(ClaudeSession {
+        let session = ClaudeSession {
             session_id: session_id.to_string(),
             project_path: display_project_path,
-            timestamp: first_timestamp.unwrap_or_else(Utc::now),
-            message_count,
-            git_branch,
-            latest_content_preview,
+            timestamp: scan.first_timestamp.unwrap_or_else(Utc::now),
+            message_count: scan.message_count,
+            git_branch: scan.git_branch.clone(),
+            latest_content_preview: scan.latest_content_preview.clone(),
             ide_info,
-            is_processing: has_incomplete_sequence,
+            is_processing: scan.has_incomplete_sequence,
             file_modified_time,
-        })
+            token_usage,
+            health: session_diagnostics::health(scan.corrupted_lines, scan.unrecognized_lines),
+        };
+
+        Ok((session, scan))
     }
 
     pub async fn get_session_messages(
         &self,
         session_id: &str,
-    ) -> Result<Vec<ClaudeMessage>, Box<dyn std::error::Error>> {
-        // Check cache first
+    ) -> ManagerResult<Vec<ClaudeMessage>> {
+        let cached_file = self
+            .messages_cache
+            .read()
+            .await
+            .get(session_id)
+            .map(|cached| cached.file_path.clone())
+            .filter(|path| path.exists());
+
+        // Find session file
+        let session_file = match cached_file {
+            Some(path) => path,
+            None => self.find_session_file(session_id)?,
+        };
+        let metadata = fs::metadata(&session_file).at_path(&session_file)?;
+        let size = metadata.len();
+        let modified = DateTime::<Utc>::from(metadata.modified().at_path(&session_file)?);
+
+        // Serve from cache while the file is unchanged
         {
             let cache = self.messages_cache.read().await;
-            if let Some(messages) = cache.get(session_id) {
-                return Ok(messages.clone());
+            if let Some(cached) = cache.get(session_id) {
+                if cached.file_path == session_file
+                    && cached.size == size
+                    && cached.modified == modified
+                {
+                    return Ok(cached.messages.clone());
+                }
             }
         }
 
-        // Find session file
-        let session_file = self.find_session_file(session_id)?;
-        let messages = self.parse_messages_file(&session_file, session_id).await?;
+        // Otherwise only parse what was appended since the last read
+        let previous = self
+            .messages_cache
+            .write()
+            .await
+            .remove(session_id)
+            .filter(|cached| cached.file_path == session_file);
+        let (cursor, mut messages) = match previous {
+            Some(cached) => (Some(cached.cursor), cached.messages),
+            None => (None, Vec::new()),
+        };
+
+        let tail = jsonl_tail::read_appended_lines(&session_file, cursor.as_ref())
+            .at_path(&session_file)?;
+        if tail.reset {
+            messages.clear();
+        }
+        self.parse_message_lines(&tail.lines, session_id, &mut messages)?;
+        Self::pair_tool_results(&mut messages);
 
         // Cache the result
         {
             let mut cache = self.messages_cache.write().await;
-            cache.insert(session_id.to_string(), messages.clone());
+            cache.insert(
+                session_id.to_string(),
+                CachedMessages {
+                    file_path: session_file,
+                    size,
+                    modified,
+                    cursor: tail.cursor,
+                    messages: messages.clone(),
+                },
+            );
         }
 
         Ok(messages)
     }
 
-    fn find_session_file(&self, session_id: &str) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    /// Lines of the session file that were skipped or not understood when parsing it
+    pub async fn get_session_diagnostics(
+        &self,
+        session_id: &str,
+    ) -> ManagerResult<SessionDiagnostics> {
+        let session_file = self.find_session_file(session_id)?;
+        session_diagnostics::diagnose_file(session_id, &session_file)
+            .at_path(&session_file)
+            .map_err(|e| e.with_session(session_id))
+    }
+
+    /// Render a session as JSON, a Markdown transcript or an HTML page
+    ///
+    /// With `options.redaction` set, secrets and personal details are replaced
+    /// in the messages before any format is rendered.
+    pub async fn export_session(
+        &self,
+        session_id: &str,
+        options: &ExportOptions,
+    ) -> ManagerResult<SessionExport> {
+        let mut messages = self.get_session_messages(session_id).await?;
+        let report = match &options.redaction {
+            Some(redaction) => Redactor::new(redaction, &RedactionContext::current())?
+                .redact_messages(&mut messages),
+            None => RedactionReport::default(),
+        };
+        let content = match options.format {
+            ExportFormat::Json => serde_json::to_string_pretty(&messages)?,
+            ExportFormat::Markdown => transcript::render_markdown(session_id, &messages, options),
+            ExportFormat::Html => transcript::render_html(session_id, &messages, options),
+        };
+        Ok(SessionExport { content, report })
+    }
+
+    /// Write a session export to `path`, returning what was redacted
+    ///
+    /// The path is chosen by the user, so unlike other writes it is not
+    /// limited to `.claude` directories. An existing file is only replaced
+    /// when `overwrite` is set.
+    pub async fn export_session_to_file(
+        &self,
+        session_id: &str,
+        options: &ExportOptions,
+        path: &Path,
+        overwrite: bool,
+    ) -> ManagerResult<RedactionReport> {
+        if !path.is_absolute() {
+            return Err(ManagerError::validation(format!(
+                "Export path must be absolute: {}",
+                path.display()
+            ))
+            .with_path(path));
+        }
+        if path.is_dir() {
+            return Err(ManagerError::validation(format!(
+                "Export path is a directory, not a file: {}",
+                path.display()
+            ))
+            .with_path(path));
+        }
+        if path.exists() && !overwrite {
+            return Err(ManagerError::already_exists(format!(
+                "Export file already exists: {}",
+                path.display()
+            ))
+            .with_path(path));
+        }
+        let export = self.export_session(session_id, options).await?;
+        file_store::write_atomic(path, export.content.as_bytes()).at_path(path)?;
+        Ok(export.report)
+    }
+
+    /// Rebuild the branching conversation from uuid/parentUuid links
+    pub async fn get_session_tree(&self, session_id: &str) -> ManagerResult<SessionTree> {
+        let session_file = self.find_session_file(session_id)?;
+        // Lossy decoding keeps one bad line from hiding the rest of the tree
+        let lines = jsonl_tail::read_appended_lines(&session_file, None)
+            .at_path(&session_file)?
+            .lines;
+
+        let mut entries = Vec::new();
+        let mut summary_leaves = Vec::new();
+
+        for line in &lines {
+            if let Ok(Some(raw)) = session_diagnostics::parse_line(line) {
+                let Ok(message) = self.parse_claude_message(&raw, session_id) else {
+                    continue;
+                };
+                if let Some(ClaudeMessage::Summary { leaf_uuid, .. }) = &message {
+                    summary_leaves.push(leaf_uuid.clone());
+                    continue;
+                }
+
+                let is_compact_boundary =
+                    raw.get("subtype").and_then(|s| s.as_str()) == Some("compact_boundary");
+
+                // Compaction starts a new root; logicalParentUuid keeps it attached
+                let parent_uuid = raw
+                    .get("parentUuid")
+                    .and_then(|u| u.as_str())
+                    .or_else(|| raw.get("logicalParentUuid").and_then(|u| u.as_str()))
+                    .map(|s| s.to_string());
+
+                let uuid = raw
+                    .get("uuid")
+                    .and_then(|u| u.as_str())
+                    .unwrap_or("")
+                    .to_string();
+
+                // Other system entries carry no conversation content
+                if message.is_none() && !is_compact_boundary {
+                    continue;
+                }
+
+                entries.push(TreeEntry {
+                    uuid,
+                    parent_uuid,
+                    is_sidechain: raw
+                        .get("isSidechain")
+                        .and_then(|s| s.as_bool())
+                        .unwrap_or(false),
+                    is_compact_boundary,
+                    message,
+                });
+            }
+        }
+
+        // Pair tool results the same way get_session_messages does
+        let mut owners = Vec::new();
+        let mut messages = Vec::new();
+        for (i, entry) in entries.iter_mut().enumerate() {
+            if let Some(message) = entry.message.take() {
+                owners.push(i);
+                messages.push(message);
+            }
+        }
+        Self::pair_tool_results(&mut messages);
+        for (i, message) in owners.into_iter().zip(messages) {
+            entries[i].message = Some(message);
+        }
+
+        Ok(conversation_tree::build_session_tree(
+            session_id,
+            entries,
+            &summary_leaves,
+        ))
+    }
+
+    /// Full-text search over message content, ranked by relevance
+    pub async fn search_messages(
+        &self,
+        query: &str,
+        limit: usize,
+    ) -> ManagerResult<Vec<MessageSearchHit>> {
+        self.get_all_sessions().await?;
+        self.catch_up_search_index().await?;
+        let index = self.search_index.read().await;
+        Ok(index.search(query, limit, |file_path, offset, session_id| {
+            self.message_text_at(file_path, offset, session_id)
+        }))
+    }
+
+    /// Sessions matching a structured query, newest first
+    pub async fn search_sessions(&self, query: &Query) -> ManagerResult<Vec<ClaudeSession>> {
+        let sessions = self.get_all_sessions().await?;
+        if query.is_empty() {
+            return Ok(sessions);
+        }
+
+        // Free text also matches message content through the full-text index
+        let mut content_matches = HashMap::new();
+        if query.text_terms().next().is_some() {
+            self.catch_up_search_index().await?;
+            let index = self.search_index.read().await;
+            for text in query.text_terms() {
+                content_matches.insert(text.to_string(), index.matching_sessions(text));
+            }
+        }
+
+        let mut results = Vec::new();
+        for session in sessions {
+            let target = SessionTarget {
+                session: &session,
+                facts: None,
+                content_matches: &content_matches,
+            };
+            if !query.may_match(&target) {
+                continue;
+            }
+
+            let facts = if query.needs_messages() {
+                Some(self.message_facts(&session.session_id).await?)
+            } else {
+                None
+            };
+            let target = SessionTarget {
+                facts: facts.as_ref(),
+                ..target
+            };
+            if query.matches(&target) {
+                results.push(session);
+            }
+        }
+
+        Ok(results)
+    }
+
+    /// Tools used and models that answered in a session
+    async fn message_facts(&self, session_id: &str) -> ManagerResult<MessageFacts> {
+        let mut facts = MessageFacts::default();
+        for message in self.get_session_messages(session_id).await? {
+            if let ClaudeMessage::Assistant { content, model, .. } = message {
+                facts.models.extend(model);
+                if let MessageContent::Assistant { content, .. } = content {
+                    for block in content {
+                        if let ContentBlock::ToolUse { name, .. } = block {
+                            facts.tools.insert(name);
+                        }
+                    }
+                }
+            }
+        }
+        Ok(facts)
+    }
+
+    /// Every session file under projects/ with its session id
+    fn session_files(&self) -> ManagerResult<Vec<(PathBuf, String)>> {
+        let projects_dir = self.claude_dir.join("projects");
+        let mut files = Vec::new();
+        if !projects_dir.exists() {
+            return Ok(files);
+        }
+
+        for entry in fs::read_dir(&projects_dir).at_path(&projects_dir)? {
+            let project_dir = entry.at_path(&projects_dir)?.path();
+            if !project_dir.is_dir() {
+                continue;
+            }
+
+            for session_file in fs::read_dir(&project_dir).at_path(&project_dir)? {
+                let file_path = session_file.at_path(&project_dir)?.path();
+                if file_path.extension().and_then(|e| e.to_str()) != Some("jsonl") {
+                    continue;
+                }
+                let session_id = file_path
+                    .file_stem()
+                    .and_then(|n| n.to_str())
+                    .unwrap_or("")
+                    .to_string();
+                files.push((file_path, session_id));
+            }
+        }
+
+        Ok(files)
+    }
+
+    fn find_session_file(&self, session_id: &str) -> ManagerResult<PathBuf> {
+        path_policy::check_file_name(session_id)?;
         let projects_dir = self.claude_dir.join("projects");
 
-        for entry in fs::read_dir(&projects_dir)? {
-            let entry = entry?;
+        for entry in fs::read_dir(&projects_dir).at_path(&projects_dir)? {
+            let entry = entry.at_path(&projects_dir)?;
             let project_path = entry.path();
 
             if project_path.is_dir() {
@@ -307,35 +907,35 @@ impl ClaudeDataManager {
             }
         }
 
-        Err(format!("Session file not found for ID: {session_id}").into())
+        Err(
+            ManagerError::not_found(format!("Session file not found for ID: {session_id}"))
+                .with_session(session_id),
+        )
     }
 
-    async fn parse_messages_file(
+    fn parse_message_lines(
         &self,
-        file_path: &Path,
+        lines: &[TailLine],
         session_id: &str,
-    ) -> Result<Vec<ClaudeMessage>, Box<dyn std::error::Error>> {
-        let file = fs::File::open(file_path)?;
-        let reader = BufReader::new(file);
-        let mut messages = Vec::new();
-
-        for line in reader.lines() {
-            let line = line?;
-            if let Ok(raw_message) = serde_json::from_str::<serde_json::Value>(&line) {
+        messages: &mut Vec<ClaudeMessage>,
+    ) -> ManagerResult<()> {
+        for line in lines {
+            // Skipped lines are reported by get_session_diagnostics
+            if let Ok(Some(raw_message)) = session_diagnostics::parse_line(line) {
                 if let Some(message) = self.parse_claude_message(&raw_message, session_id)? {
                     messages.push(message);
                 }
             }
         }
 
-        Ok(messages)
+        Ok(())
     }
 
     fn parse_claude_message(
         &self,
         raw: &serde_json::Value,
         session_id: &str,
-    ) -> Result<Option<ClaudeMessage>, Box<dyn std::error::Error>> {
+    ) -> ManagerResult<Option<ClaudeMessage>> {
         let uuid = raw
             .get("uuid")
             .and_then(|u| u.as_str())
@@ -366,16 +966,29 @@ impl ClaudeDataManager {
 
         let message = match raw.get("type").and_then(|t| t.as_str()) {
             Some("user") => {
-                let content_text = raw
-                    .get("message")
-                    .and_then(|m| m.get("content"))
-                    .and_then(|c| c.as_str())
-                    .unwrap_or("")
-                    .to_string();
+                let raw_content = raw.get("message").and_then(|m| m.get("content"));
+
+                // Tool results are stored as user messages with an array of blocks
+                let content = match raw_content.and_then(|c| c.as_array()) {
+                    Some(blocks) => {
+                        let mut content_blocks: Vec<ContentBlock> = blocks
+                            .iter()
+                            .filter_map(|block| self.parse_content_block(block))
+                            .collect();
+                        Self::detach_large_images(&mut content_blocks, &uuid, &mut Vec::new());
 
-                let content = MessageContent::User {
-                    role: "user".to_string(),
-                    content: content_text,
+                        MessageContent::UserBlocks {
+                            role: "user".to_string(),
+                            content: content_blocks,
+                        }
+                    }
+                    None => MessageContent::User {
+                        role: "user".to_string(),
+                        content: raw_content
+                            .and_then(|c| c.as_str())
+                            .unwrap_or("")
+                            .to_string(),
+                    },
                 };
 
                 // User messages are always completed when they exist
@@ -393,7 +1006,7 @@ impl ClaudeDataManager {
                 }
             }
             Some("assistant") => {
-                let content_blocks: Vec<crate::models::ContentBlock> = raw
+                let mut content_blocks: Vec<crate::models::ContentBlock> = raw
                     .get("message")
                     .and_then(|m| m.get("content"))
                     .and_then(|c| c.as_array())
@@ -404,6 +1017,7 @@ impl ClaudeDataManager {
                             .collect()
                     })
                     .unwrap_or_default();
+                Self::detach_large_images(&mut content_blocks, &uuid, &mut Vec::new());
 
                 // Extract stop_reason and determine processing status before moving content_blocks
                 let stop_reason = raw
@@ -424,6 +1038,22 @@ impl ClaudeDataManager {
                     None => crate::models::ProcessingStatus::Processing,
                 };
 
+                let model = raw
+                    .get("message")
+                    .and_then(|m| m.get("model"))
+                    .and_then(|m| m.as_str())
+                    .map(|s| s.to_string());
+
+                let usage = raw
+                    .get("message")
+                    .and_then(|m| m.get("usage"))
+                    .map(Self::parse_token_usage);
+
+                let request_id = raw
+                    .get("requestId")
+                    .and_then(|r| r.as_str())
+                    .map(|s| s.to_string());
+
                 let content = MessageContent::Assistant {
                     role: "assistant".to_string(),
                     content: content_blocks,
@@ -439,6 +1069,9 @@ impl ClaudeDataManager {
                     git_branch,
                     processing_status,
                     stop_reason,
+                    model,
+                    usage,
+                    request_id,
                 }
             }
             Some("summary") => {
@@ -462,6 +1095,17 @@ impl ClaudeDataManager {
         Ok(Some(message))
     }
 
+    fn parse_token_usage(usage: &serde_json::Value) -> TokenUsage {
+        let field = |name: &str| usage.get(name).and_then(|v| v.as_u64()).unwrap_or(0);
+
+        TokenUsage {
+            input_tokens: field("input_tokens"),
+            output_tokens: field("output_tokens"),
+            cache_creation_input_tokens: field("cache_creation_input_tokens"),
+            cache_read_input_tokens: field("cache_read_input_tokens"),
+        }
+    }
+
     fn parse_content_block(&self, block: &serde_json::Value) -> Option<ContentBlock> {
         match block.get("type").and_then(|t| t.as_str()) {
             Some("text") => {
@@ -488,12 +1132,229 @@ impl ClaudeDataManager {
                     .cloned()
                     .unwrap_or(serde_json::Value::Null);
 
-                Some(ContentBlock::ToolUse { id, name, input })
+                Some(ContentBlock::ToolUse {
+                    id,
+                    name,
+                    input,
+                    result: None,
+                })
+            }
+            Some("tool_result") => {
+                let tool_use_id = block
+                    .get("tool_use_id")
+                    .and_then(|i| i.as_str())
+                    .unwrap_or("")
+                    .to_string();
+
+                // Result content is either a plain string or a list of blocks
+                let content = match block.get("content") {
+                    Some(serde_json::Value::String(text)) => {
+                        vec![ContentBlock::Text { text: text.clone() }]
+                    }
+                    Some(serde_json::Value::Array(blocks)) => blocks
+                        .iter()
+                        .filter_map(|b| self.parse_content_block(b))
+                        .collect(),
+                    _ => Vec::new(),
+                };
+
+                let is_error = block
+                    .get("is_error")
+                    .and_then(|e| e.as_bool())
+                    .unwrap_or(false);
+
+                Some(ContentBlock::ToolResult {
+                    tool_use_id,
+                    content,
+                    is_error,
+                })
+            }
+            Some("thinking") => {
+                let thinking = block
+                    .get("thinking")
+                    .and_then(|t| t.as_str())
+                    .unwrap_or("")
+                    .to_string();
+                let signature = block
+                    .get("signature")
+                    .and_then(|s| s.as_str())
+                    .map(|s| s.to_string());
+
+                Some(ContentBlock::Thinking {
+                    thinking,
+                    signature,
+                })
+            }
+            Some("redacted_thinking") => {
+                let data = block
+                    .get("data")
+                    .and_then(|d| d.as_str())
+                    .unwrap_or("")
+                    .to_string();
+
+                Some(ContentBlock::RedactedThinking { data })
+            }
+            Some("image") => {
+                let source = block.get("source")?;
+                let media_type = source
+                    .get("media_type")
+                    .and_then(|m| m.as_str())
+                    .unwrap_or("image/png")
+                    .to_string();
+
+                let source = match source.get("type").and_then(|t| t.as_str()) {
+                    Some("base64") => ImageSource::Base64 {
+                        data: source.get("data")?.as_str()?.to_string(),
+                    },
+                    Some("url") => ImageSource::Url {
+                        url: source.get("url")?.as_str()?.to_string(),
+                    },
+                    _ => return None,
+                };
+
+                Some(ContentBlock::Image { media_type, source })
+            }
+            _ => None,
+        }
+    }
+
+    /// Replace large inline images with handles so message lists stay small.
+    /// `block_path` is the index path to the block, descending into tool results.
+    fn detach_large_images(
+        blocks: &mut [ContentBlock],
+        message_uuid: &str,
+        block_path: &mut Vec<usize>,
+    ) {
+        for (index, block) in blocks.iter_mut().enumerate() {
+            block_path.push(index);
+            match block {
+                ContentBlock::Image { source, .. } => {
+                    if let ImageSource::Base64 { data } = source {
+                        if data.len() > INLINE_IMAGE_MAX_BYTES {
+                            *source = ImageSource::Handle {
+                                message_uuid: message_uuid.to_string(),
+                                block_path: block_path.clone(),
+                                size: data.len(),
+                            };
+                        }
+                    }
+                }
+                ContentBlock::ToolResult { content, .. } => {
+                    Self::detach_large_images(content, message_uuid, block_path);
+                }
+                _ => {}
+            }
+            block_path.pop();
+        }
+    }
+
+    /// Load the base64 payload of an image that was replaced by a handle
+    pub async fn get_image_data(
+        &self,
+        session_id: &str,
+        message_uuid: &str,
+        block_path: &[usize],
+    ) -> ManagerResult<String> {
+        let session_file = self.find_session_file(session_id)?;
+        let reader = BufReader::new(fs::File::open(&session_file).at_path(&session_file)?);
+
+        for line in reader.lines() {
+            let line = line.at_path(&session_file)?;
+            if let Ok(raw) = serde_json::from_str::<serde_json::Value>(&line) {
+                if raw.get("uuid").and_then(|u| u.as_str()) != Some(message_uuid) {
+                    continue;
+                }
+
+                let blocks: Vec<ContentBlock> = raw
+                    .get("message")
+                    .and_then(|m| m.get("content"))
+                    .and_then(|c| c.as_array())
+                    .map(|blocks| {
+                        blocks
+                            .iter()
+                            .filter_map(|block| self.parse_content_block(block))
+                            .collect()
+                    })
+                    .unwrap_or_default();
+
+                return Self::find_image_data(&blocks, block_path).ok_or_else(|| {
+                    ManagerError::not_found(format!(
+                        "Image not found at {block_path:?} in message {message_uuid}"
+                    ))
+                    .with_session(session_id)
+                });
             }
+        }
+
+        Err(ManagerError::not_found(format!(
+            "Message {message_uuid} not found in session {session_id}"
+        ))
+        .with_session(session_id))
+    }
+
+    fn find_image_data(blocks: &[ContentBlock], block_path: &[usize]) -> Option<String> {
+        let (index, rest) = block_path.split_first()?;
+
+        match blocks.get(*index)? {
+            ContentBlock::Image {
+                source: ImageSource::Base64 { data },
+                ..
+            } if rest.is_empty() => Some(data.clone()),
+            ContentBlock::ToolResult { content, .. } => Self::find_image_data(content, rest),
             _ => None,
         }
     }
 
+    /// Attach each tool_result to the tool_use block that requested it
+    fn pair_tool_results(messages: &mut [ClaudeMessage]) {
+        let mut results: HashMap<String, ToolResult> = HashMap::new();
+
+        for message in messages.iter() {
+            if let ClaudeMessage::User {
+                uuid,
+                content: MessageContent::UserBlocks { content, .. },
+                ..
+            } = message
+            {
+                for block in content {
+                    if let ContentBlock::ToolResult {
+                        tool_use_id,
+                        content,
+                        is_error,
+                    } = block
+                    {
+                        results.insert(
+                            tool_use_id.clone(),
+                            ToolResult {
+                                content: content.clone(),
+                                is_error: *is_error,
+                                message_uuid: uuid.clone(),
+                            },
+                        );
+                    }
+                }
+            }
+        }
+
+        if results.is_empty() {
+            return;
+        }
+
+        for message in messages.iter_mut() {
+            if let ClaudeMessage::Assistant {
+                content: MessageContent::Assistant { content, .. },
+                ..
+            } = message
+            {
+                for block in content.iter_mut() {
+                    if let ContentBlock::ToolUse { id, result, .. } = block {
+                        *result = results.remove(id.as_str());
+                    }
+                }
+            }
+        }
+    }
+
     fn extract_content_preview(&self, message: &serde_json::Value) -> Option<String> {
         let msg_type = message.get("type").and_then(|t| t.as_str())?;
 
@@ -576,33 +1437,77 @@ impl ClaudeDataManager {
         }
     }
 
-    pub async fn get_command_history(
-        &self,
-    ) -> Result<Vec<CommandLogEntry>, Box<dyn std::error::Error>> {
+    pub async fn get_command_history(&self) -> ManagerResult<Vec<CommandLogEntry>> {
         let log_file = self.claude_dir.join("command_history.log");
-
-        if !log_file.exists() {
-            return Ok(Vec::new());
+        let mut log_entries = Vec::new();
+
+        // The log only exists when a logging hook is set up
+        if log_file.exists() {
+            let content = fs::read_to_string(&log_file).at_path(&log_file)?;
+            for line in content.lines() {
+                if let Some(entry) = self.parse_command_log_line(line) {
+                    log_entries.push(entry);
+                }
+            }
         }
 
-        let content = fs::read_to_string(&log_file)?;
-        let mut entries = Vec::new();
+        let session_entries = self.refresh_bash_commands().await?;
+        Ok(bash_history::merge_command_history(
+            log_entries,
+            session_entries,
+        ))
+    }
 
-        for line in content.lines() {
-            if let Some(entry) = self.parse_command_log_line(line) {
-                entries.push(entry);
-            }
+    /// Frequency, failure rate and per-project/per-day breakdowns of the command timeline
+    pub async fn get_command_analytics(&self, limit: usize) -> ManagerResult<CommandAnalytics> {
+        let entries = self.get_command_history().await?;
+        let project_paths: HashMap<String, String> = self
+            .get_all_sessions()
+            .await?
+            .into_iter()
+            .map(|session| (session.session_id, session.project_path))
+            .collect();
+
+        // Commands run from sessions count towards the session's project
+        Ok(command_analytics::build_command_analytics(
+            &entries,
+            |entry| {
+                entry
+                    .session_id
+                    .as_ref()
+                    .and_then(|id| project_paths.get(id).cloned())
+                    .or_else(|| entry.cwd.clone())
+            },
+            limit,
+        ))
+    }
+
+    /// Extract Bash calls appended to session files since the last refresh
+    async fn refresh_bash_commands(&self) -> ManagerResult<Vec<CommandLogEntry>> {
+        let mut index = self.bash_commands.write().await;
+        let mut seen_files = HashSet::new();
+        for (file_path, session_id) in self.session_files()? {
+            let read = jsonl_tail::read_appended_lines(&file_path, index.cursor(&file_path))
+                .at_path(&file_path)?;
+            index.update_file(
+                &file_path,
+                &session_id,
+                read.reset,
+                read.cursor,
+                &read.lines,
+            );
+            seen_files.insert(file_path);
         }
+        index.retain(&seen_files);
 
-        entries.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
-        Ok(entries)
+        Ok(index.entries().cloned().collect())
     }
 
     fn parse_command_log_line(&self, line: &str) -> Option<CommandLogEntry> {
         // Parse format: [Thu Jul 17 15:18:23 JST 2025] user: command
         if let Some(start) = line.find('[') {
             if let Some(end) = line.find(']') {
-                let _timestamp_str = &line[start + 1..end];
+                let timestamp_str = &line[start + 1..end];
 
                 // Look for '] ' pattern and skip it using character boundaries
                 let pattern = "] ";
@@ -628,14 +1533,22 @@ impl ClaudeDataManager {
                             if cmd_pos < remaining.len() {
                                 let command = &remaining[cmd_pos..];
 
-                                // Try to parse timestamp (simplified)
-                                let timestamp = Utc::now(); // For now, use current time
+                                // Unrecognized timestamps stay None rather than a made-up time
+                                let timestamp = parse_log_timestamp(timestamp_str);
 
                                 return Some(CommandLogEntry {
                                     timestamp,
+                                    raw_timestamp: timestamp_str.to_string(),
                                     user: user_part.to_string(),
                                     command: command.to_string(),
                                     cwd: None,
+                                    source: CommandSource::HistoryLog,
+                                    session_id: None,
+                                    git_branch: None,
+                                    description: None,
+                                    status: None,
+                                    exit_code: None,
+                                    duration_ms: None,
                                 });
                             }
                         }
@@ -646,7 +1559,7 @@ impl ClaudeDataManager {
         None
     }
 
-    pub async fn get_todos(&self) -> Result<Vec<TodoItem>, Box<dyn std::error::Error>> {
+    pub async fn get_todos(&self) -> ManagerResult<Vec<TodoItem>> {
         let todos_dir = self.claude_dir.join("todos");
         let mut all_todos = Vec::new();
 
@@ -654,8 +1567,8 @@ impl ClaudeDataManager {
             return Ok(all_todos);
         }
 
-        for entry in fs::read_dir(&todos_dir)? {
-            let entry = entry?;
+        for entry in fs::read_dir(&todos_dir).at_path(&todos_dir)? {
+            let entry = entry.at_path(&todos_dir)?;
             let file_path = entry.path();
 
             if file_path.extension().and_then(|e| e.to_str()) == Some("json") {
@@ -670,21 +1583,20 @@ impl ClaudeDataManager {
         Ok(all_todos)
     }
 
-    pub async fn get_settings(&self) -> Result<ClaudeSettings, Box<dyn std::error::Error>> {
+    pub async fn get_settings(&self) -> ManagerResult<ClaudeSettings> {
         let settings_file = self.claude_dir.join("settings.json");
 
         if !settings_file.exists() {
-            return Err("Settings file not found".into());
+            return Err(
+                ManagerError::not_found("Settings file not found").with_path(&settings_file)
+            );
         }
 
-        let content = fs::read_to_string(&settings_file)?;
-        let settings = serde_json::from_str(&content)?;
-        Ok(settings)
+        let content = fs::read_to_string(&settings_file).at_path(&settings_file)?;
+        serde_json::from_str(&content).at_path(&settings_file)
     }
 
-    pub async fn get_project_summary(
-        &self,
-    ) -> Result<Vec<ProjectSummary>, Box<dyn std::error::Error>> {
+    pub async fn get_project_summary(&self) -> ManagerResult<Vec<ProjectSummary>> {
         let sessions = self.get_all_sessions().await?;
         let mut project_map: HashMap<String, ProjectSummary> = HashMap::new();
 
@@ -733,7 +1645,7 @@ impl ClaudeDataManager {
         Ok(projects)
     }
 
-    pub async fn get_session_stats(&self) -> Result<SessionStats, Box<dyn std::error::Error>> {
+    pub async fn get_session_stats(&self) -> ManagerResult<SessionStats> {
         let sessions = self.get_all_sessions().await?;
         let commands = self.get_command_history().await?;
         let todos = self.get_todos().await?;
@@ -758,19 +1670,42 @@ impl ClaudeDataManager {
         })
     }
 
-    async fn get_file_modified_time(
-        &self,
-        path: &Path,
-    ) -> Result<DateTime<Utc>, Box<dyn std::error::Error>> {
-        let metadata = fs::metadata(path)?;
-        let modified = metadata.modified()?;
+    /// Estimate spend across all sessions using the given price table
+    pub async fn get_cost_report(&self, price_table: &PriceTable) -> ManagerResult<CostReport> {
+        // Refreshes the session index, whose scans already hold usage per request
+        self.get_all_sessions().await?;
+        let index = self.session_index.read().await;
+        let mut builder = CostReportBuilder::new(price_table);
+
+        for (session, scan) in index.entries() {
+            for request in scan.usage_by_request.values() {
+                let Some(timestamp) = request.timestamp else {
+                    continue;
+                };
+                if request.usage.total() == 0 {
+                    continue;
+                }
+                builder.add(
+                    &session.session_id,
+                    &session.project_path,
+                    timestamp,
+                    request.model.as_deref().unwrap_or("unknown"),
+                    &request.usage,
+                );
+            }
+        }
+
+        Ok(builder.build())
+    }
+
+    async fn get_file_modified_time(&self, path: &Path) -> ManagerResult<DateTime<Utc>> {
+        let metadata = fs::metadata(path).at_path(path)?;
+        let modified = metadata.modified().at_path(path)?;
         let datetime = DateTime::<Utc>::from(modified);
         Ok(datetime)
     }
 
-    pub async fn get_changed_sessions(
-        &self,
-    ) -> Result<Vec<ClaudeSession>, Box<dyn std::error::Error>> {
+    pub async fn get_changed_sessions(&self) -> ManagerResult<Vec<ClaudeSession>> {
         let projects_dir = self.claude_dir.join("projects");
         let mut changed_sessions = Vec::new();
         let mut timestamps = self.file_timestamps.write().await;
@@ -779,8 +1714,8 @@ impl ClaudeDataManager {
             return Ok(changed_sessions);
         }
 
-        for entry in fs::read_dir(&projects_dir)? {
-            let entry = entry?;
+        for entry in fs::read_dir(&projects_dir).at_path(&projects_dir)? {
+            let entry = entry.at_path(&projects_dir)?;
             let project_path = entry.path();
 
             if project_path.is_dir() {
@@ -790,8 +1725,8 @@ impl ClaudeDataManager {
                     .unwrap_or("")
                     .to_string();
 
-                for session_file in fs::read_dir(&project_path)? {
-                    let session_file = session_file?;
+                for session_file in fs::read_dir(&project_path).at_path(&project_path)? {
+                    let session_file = session_file.at_path(&project_path)?;
                     let file_path = session_file.path();
 
                     if file_path.extension().and_then(|e| e.to_str()) == Some("jsonl") {
@@ -806,7 +1741,7 @@ impl ClaudeDataManager {
                                 .to_string();
 
                             let session = self
-                                .parse_session_file(&file_path, &session_id, &project_name)
+                                .load_session_summary(&file_path, &session_id, &project_name)
                                 .await?;
                             changed_sessions.push(session);
                             timestamps.insert(file_path.clone(), current_time);
@@ -887,10 +1822,7 @@ impl ClaudeDataManager {
         None
     }
 
-    pub async fn activate_ide_window(
-        &self,
-        ide_info: &IdeInfo,
-    ) -> Result<(), Box<dyn std::error::Error>> {
+    pub async fn activate_ide_window(&self, ide_info: &IdeInfo) -> ManagerResult<()> {
         #[cfg(target_os = "macos")]
         {
             // Use AppleScript to bring VS Code window to front on macOS
@@ -913,22 +1845,24 @@ impl ClaudeDataManager {
         {
             // Windows implementation would go here
             // For now, return an error
-            return Err("Window activation not yet implemented for Windows".into());
+            return Err(ManagerError::unsupported(
+                "Window activation not yet implemented for Windows",
+            ));
         }
 
         #[cfg(target_os = "linux")]
         {
             // Linux implementation would go here
             // For now, return an error
-            return Err("Window activation not yet implemented for Linux".into());
+            return Err(ManagerError::unsupported(
+                "Window activation not yet implemented for Linux",
+            ));
         }
 
         Ok(())
     }
 
-    pub async fn get_project_path_mapping(
-        &self,
-    ) -> Result<HashMap<String, String>, Box<dyn std::error::Error>> {
+    pub async fn get_project_path_mapping(&self) -> ManagerResult<HashMap<String, String>> {
         let mut mapping = HashMap::new();
         let projects_dir = self.claude_dir.join("projects");
 
@@ -936,8 +1870,8 @@ impl ClaudeDataManager {
             return Ok(mapping);
         }
 
-        for entry in fs::read_dir(&projects_dir)? {
-            let entry = entry?;
+        for entry in fs::read_dir(&projects_dir).at_path(&projects_dir)? {
+            let entry = entry.at_path(&projects_dir)?;
             let encoded_path = entry.file_name().to_string_lossy().to_string();
 
             if !encoded_path.starts_with('-') {
@@ -967,15 +1901,15 @@ impl ClaudeDataManager {
     pub async fn extract_cwd_from_session_file(
         &self,
         file_path: &Path,
-    ) -> Result<Option<String>, Box<dyn std::error::Error>> {
-        let file = fs::File::open(file_path)?;
+    ) -> ManagerResult<Option<String>> {
+        let file = fs::File::open(file_path).at_path(file_path)?;
         let reader = BufReader::new(file);
         let mut lines = reader.lines();
 
         // Read only the first few lines to avoid parsing entire large files
         for _ in 0..10 {
             if let Some(line) = lines.next() {
-                let line = line?;
+                let line = line.at_path(file_path)?;
                 if let Ok(message) = serde_json::from_str::<serde_json::Value>(&line) {
                     // Extract cwd field directly from JSON
                     if let Some(cwd) = message.get("cwd").and_then(|c| c.as_str()) {
@@ -995,7 +1929,7 @@ impl ClaudeDataManager {
     pub async fn get_claude_directory_info(
         &self,
         project_path: &str,
-    ) -> Result<ClaudeDirectoryInfo, Box<dyn std::error::Error>> {
+    ) -> ManagerResult<ClaudeDirectoryInfo> {
         let claude_dir_path = PathBuf::from(project_path).join(".claude");
         let exists = claude_dir_path.exists() && claude_dir_path.is_dir();
 
@@ -1016,11 +1950,11 @@ impl ClaudeDataManager {
         base_path: &Path,
         current_path: &Path,
         files: &mut Vec<ClaudeDirectoryFile>,
-    ) -> Result<(), Box<dyn std::error::Error>> {
+    ) -> ManagerResult<()> {
         if let Ok(entries) = fs::read_dir(current_path) {
             for entry in entries.flatten() {
                 let path = entry.path();
-                let metadata = entry.metadata()?;
+                let metadata = entry.metadata().at_path(&path)?;
 
                 // Get relative path from the base .claude directory
                 let relative_path = path
@@ -1033,7 +1967,7 @@ impl ClaudeDataManager {
                     name: relative_path,
                     path: path.to_string_lossy().to_string(),
                     size: metadata.len(),
-                    modified: DateTime::<Utc>::from(metadata.modified()?),
+                    modified: DateTime::<Utc>::from(metadata.modified().at_path(&path)?),
                     is_directory: metadata.is_dir(),
                 };
 
@@ -1049,65 +1983,50 @@ impl ClaudeDataManager {
         Ok(())
     }
 
-    pub async fn read_claude_file(
-        &self,
-        file_path: &str,
-    ) -> Result<String, Box<dyn std::error::Error>> {
-        let path = PathBuf::from(file_path);
-
-        // Security check: ensure the file is within a .claude directory
-        let mut current = path.as_path();
-        let mut is_in_claude_dir = false;
-
-        while let Some(parent) = current.parent() {
-            if current.file_name() == Some(std::ffi::OsStr::new(".claude")) {
-                is_in_claude_dir = true;
-                break;
-            }
-            current = parent;
-        }
-
-        if !is_in_claude_dir {
-            return Err("File must be within a .claude directory".into());
-        }
+    /// Resolve `file_path`, refusing anything outside ~/.claude and known projects' .claude
+    async fn claude_file_path(&self, file_path: &str) -> ManagerResult<PathBuf> {
+        let projects = self.get_project_path_mapping().await?;
+        PathPolicy::new(&self.claude_dir, projects.into_values().map(PathBuf::from))
+            .resolve(Path::new(file_path))
+    }
 
-        fs::read_to_string(&path).map_err(|e| e.into())
+    pub async fn read_claude_file(&self, file_path: &str) -> ManagerResult<String> {
+        let path = self.claude_file_path(file_path).await?;
+        fs::read_to_string(&path).at_path(&path)
     }
 
+    /// Write a file atomically and return the version of the new content
+    ///
+    /// With `expected_version`, the write fails if the file changed since it was read.
     pub async fn write_claude_file(
         &self,
         file_path: &str,
         content: &str,
-    ) -> Result<(), Box<dyn std::error::Error>> {
-        let path = PathBuf::from(file_path);
-
-        // Security check: ensure the file is within a .claude directory
-        let mut current = path.as_path();
-        let mut is_in_claude_dir = false;
-
-        while let Some(parent) = current.parent() {
-            if current.file_name() == Some(std::ffi::OsStr::new(".claude")) {
-                is_in_claude_dir = true;
-                break;
-            }
-            current = parent;
-        }
+        expected_version: Option<&str>,
+    ) -> ManagerResult<String> {
+        let path = self.claude_file_path(file_path).await?;
+        self.file_store.write(&path, content, expected_version)
+    }
 
-        if !is_in_claude_dir {
-            return Err("File must be within a .claude directory".into());
-        }
+    /// Concurrency token of a file's current content, or None if it does not exist
+    pub async fn get_file_version(&self, file_path: &str) -> ManagerResult<Option<String>> {
+        let path = self.claude_file_path(file_path).await?;
+        file_store::file_version(&path).at_path(&path)
+    }
 
-        // Create parent directories if they don't exist
-        if let Some(parent) = path.parent() {
-            fs::create_dir_all(parent)?;
-        }
+    /// Previous versions of a file, newest first
+    pub async fn list_backups(&self, file_path: &str) -> ManagerResult<Vec<FileBackup>> {
+        let path = self.claude_file_path(file_path).await?;
+        self.file_store.list_backups(&path).at_path(&path)
+    }
 
-        fs::write(&path, content).map_err(|e| e.into())
+    /// Put a previous version of a file back, backing up the current one first
+    pub async fn restore_backup(&self, file_path: &str, backup_id: &str) -> ManagerResult<String> {
+        let path = self.claude_file_path(file_path).await?;
+        self.file_store.restore_backup(&path, backup_id)
     }
 
-    pub async fn get_custom_commands(
-        &self,
-    ) -> Result<Vec<CustomCommand>, Box<dyn std::error::Error>> {
+    pub async fn get_custom_commands(&self) -> ManagerResult<Vec<CustomCommand>> {
         let commands_dir = self.claude_dir.join("commands");
         let mut commands = Vec::new();
 
@@ -1115,8 +2034,8 @@ impl ClaudeDataManager {
             return Ok(commands);
         }
 
-        for entry in fs::read_dir(&commands_dir)? {
-            let entry = entry?;
+        for entry in fs::read_dir(&commands_dir).at_path(&commands_dir)? {
+            let entry = entry.at_path(&commands_dir)?;
             let file_path = entry.path();
 
             if file_path.extension().and_then(|e| e.to_str()) == Some("md") {
@@ -1135,7 +2054,7 @@ impl ClaudeDataManager {
         Ok(commands)
     }
 
-    pub async fn get_agents(&self) -> Result<Vec<Agent>, Box<dyn std::error::Error>> {
+    pub async fn get_agents(&self) -> ManagerResult<Vec<Agent>> {
         let agents_dir = self.claude_dir.join("agents");
         let mut agents = Vec::new();
 
@@ -1143,8 +2062,8 @@ impl ClaudeDataManager {
             return Ok(agents);
         }
 
-        for entry in fs::read_dir(&agents_dir)? {
-            let entry = entry?;
+        for entry in fs::read_dir(&agents_dir).at_path(&agents_dir)? {
+            let entry = entry.at_path(&agents_dir)?;
             let file_path = entry.path();
 
             if file_path.extension().and_then(|e| e.to_str()) == Some("md") {
@@ -1169,14 +2088,14 @@ impl ClaudeDataManager {
         directory: MarkdownDirectory,
         name: &str,
         content: &str,
-    ) -> Result<(), Box<dyn std::error::Error>> {
-        let target_dir = self.claude_dir.join(directory.as_str());
-        fs::create_dir_all(&target_dir)?;
-
-        let file_path = target_dir.join(format!("{}.md", name));
-        fs::write(&file_path, content)?;
-
-        Ok(())
+        expected_version: Option<&str>,
+    ) -> ManagerResult<String> {
+        path_policy::check_file_name(name)?;
+        let file_path = self
+            .claude_dir
+            .join(directory.as_str())
+            .join(format!("{}.md", name));
+        self.file_store.write(&file_path, content, expected_version)
     }
 
     /// Generic method to delete a markdown file from a specified directory
@@ -1184,12 +2103,13 @@ impl ClaudeDataManager {
         &self,
         directory: MarkdownDirectory,
         name: &str,
-    ) -> Result<(), Box<dyn std::error::Error>> {
+    ) -> ManagerResult<()> {
+        path_policy::check_file_name(name)?;
         let file_path = self
             .claude_dir
             .join(directory.as_str())
             .join(format!("{}.md", name));
-        fs::remove_file(&file_path)?;
+        fs::remove_file(&file_path).at_path(&file_path)?;
         Ok(())
     }
 
@@ -1197,8 +2117,9 @@ impl ClaudeDataManager {
         &self,
         name: &str,
         content: &str,
-    ) -> Result<(), Box<dyn std::error::Error>> {
-        self.save_markdown_file(MarkdownDirectory::Commands, name, content)
+        expected_version: Option<&str>,
+    ) -> ManagerResult<String> {
+        self.save_markdown_file(MarkdownDirectory::Commands, name, content, expected_version)
             .await
     }
 
@@ -1206,20 +2127,18 @@ impl ClaudeDataManager {
         &self,
         name: &str,
         content: &str,
-    ) -> Result<(), Box<dyn std::error::Error>> {
-        self.save_markdown_file(MarkdownDirectory::Agents, name, content)
+        expected_version: Option<&str>,
+    ) -> ManagerResult<String> {
+        self.save_markdown_file(MarkdownDirectory::Agents, name, content, expected_version)
             .await
     }
 
-    pub async fn delete_custom_command(
-        &self,
-        name: &str,
-    ) -> Result<(), Box<dyn std::error::Error>> {
+    pub async fn delete_custom_command(&self, name: &str) -> ManagerResult<()> {
         self.delete_markdown_file(MarkdownDirectory::Commands, name)
             .await
     }
 
-    pub async fn delete_agent(&self, name: &str) -> Result<(), Box<dyn std::error::Error>> {
+    pub async fn delete_agent(&self, name: &str) -> ManagerResult<()> {
         self.delete_markdown_file(MarkdownDirectory::Agents, name)
             .await
     }
@@ -1230,56 +2149,58 @@ impl ClaudeDataManager {
         directory: MarkdownDirectory,
         old_name: &str,
         new_name: &str,
-    ) -> Result<(), Box<dyn std::error::Error>> {
+    ) -> ManagerResult<()> {
+        path_policy::check_file_name(old_name)?;
+        path_policy::check_file_name(new_name)?;
         let target_dir = self.claude_dir.join(directory.as_str());
         let old_path = target_dir.join(format!("{}.md", old_name));
         let new_path = target_dir.join(format!("{}.md", new_name));
 
         if !old_path.exists() {
-            return Err(format!("{} '{}' not found", directory.item_type(), old_name).into());
+            return Err(ManagerError::not_found(format!(
+                "{} '{}' not found",
+                directory.item_type(),
+                old_name
+            ))
+            .with_path(&old_path));
         }
 
         if new_path.exists() {
-            return Err(format!("{} '{}' already exists", directory.item_type(), new_name).into());
+            return Err(ManagerError::already_exists(format!(
+                "{} '{}' already exists",
+                directory.item_type(),
+                new_name
+            ))
+            .with_path(&new_path));
         }
 
-        fs::rename(&old_path, &new_path)?;
+        fs::rename(&old_path, &new_path).at_path(&old_path)?;
         Ok(())
     }
 
-    pub async fn rename_custom_command(
-        &self,
-        old_name: &str,
-        new_name: &str,
-    ) -> Result<(), Box<dyn std::error::Error>> {
+    pub async fn rename_custom_command(&self, old_name: &str, new_name: &str) -> ManagerResult<()> {
         self.rename_markdown_file(MarkdownDirectory::Commands, old_name, new_name)
             .await
     }
 
-    pub async fn rename_agent(
-        &self,
-        old_name: &str,
-        new_name: &str,
-    ) -> Result<(), Box<dyn std::error::Error>> {
+    pub async fn rename_agent(&self, old_name: &str, new_name: &str) -> ManagerResult<()> {
         self.rename_markdown_file(MarkdownDirectory::Agents, old_name, new_name)
             .await
     }
 
-    pub async fn get_all_settings_files(
-        &self,
-    ) -> Result<Vec<(String, String)>, Box<dyn std::error::Error>> {
+    pub async fn get_all_settings_files(&self) -> ManagerResult<Vec<(String, String)>> {
         let mut settings_files = Vec::new();
 
         // Get main settings.json
         let main_settings = self.claude_dir.join("settings.json");
         if main_settings.exists() {
-            let content = fs::read_to_string(&main_settings)?;
+            let content = fs::read_to_string(&main_settings).at_path(&main_settings)?;
             settings_files.push(("settings.json".to_string(), content));
         }
 
         // Look for settings.*.json files
-        for entry in fs::read_dir(&self.claude_dir)? {
-            let entry = entry?;
+        for entry in fs::read_dir(&self.claude_dir).at_path(&self.claude_dir)? {
+            let entry = entry.at_path(&self.claude_dir)?;
             let file_name = entry.file_name();
             let file_name_str = file_name.to_string_lossy();
 
@@ -1298,23 +2219,249 @@ impl ClaudeDataManager {
         Ok(settings_files)
     }
 
+    /// Check settings content without saving it
+    ///
+    /// The settings files edited here apply to every project, so relative hook
+    /// paths have no directory to be checked against.
+    pub fn validate_settings(&self, content: &str) -> Vec<SettingsDiagnostic> {
+        settings_validation::validate_settings(content, std::env::var_os("PATH").as_deref(), None)
+    }
+
+    /// Validate and write a settings file; errors refuse the save unless `force` is set
+    ///
+    /// With `expected_version`, the save fails if the file changed since it was read.
     pub async fn save_settings_file(
         &self,
         filename: &str,
         content: &str,
-    ) -> Result<(), Box<dyn std::error::Error>> {
+        force: bool,
+        expected_version: Option<&str>,
+    ) -> ManagerResult<SettingsSaveResult> {
         // Security check: ensure filename is safe
         if !filename.starts_with("settings.") || !filename.ends_with(".json") {
-            return Err("Invalid settings filename".into());
+            return Err(ManagerError::validation(format!(
+                "Invalid settings filename: {filename}"
+            )));
+        }
+        path_policy::check_file_name(filename)?;
+
+        let diagnostics = self.validate_settings(content);
+        let has_errors = diagnostics
+            .iter()
+            .any(|d| d.severity == DiagnosticSeverity::Error);
+        if has_errors && !force {
+            return Ok(SettingsSaveResult {
+                saved: false,
+                diagnostics,
+                version: None,
+            });
         }
 
         let file_path = self.claude_dir.join(filename);
-        fs::write(&file_path, content)?;
+        let version = self
+            .file_store
+            .write(&file_path, content, expected_version)?;
+        Ok(SettingsSaveResult {
+            saved: true,
+            diagnostics,
+            version: Some(version),
+        })
+    }
+
+    /// Allow rules that would have saved the prompts for tool calls approved in past sessions
+    pub async fn suggest_permission_rules(
+        &self,
+        limit: usize,
+    ) -> ManagerResult<Vec<PermissionSuggestion>> {
+        let mut calls: Vec<ObservedToolCall> = Vec::new();
+        for (file_path, session_id) in self.session_files()? {
+            let Ok(content) = fs::read_to_string(&file_path) else {
+                continue;
+            };
+            calls.extend(permission_suggestions::approved_tool_calls(
+                content.lines(),
+                &session_id,
+            ));
+        }
+
+        // Calls that a rule or the permission mode let through never prompted
+        let mut policies: HashMap<Option<String>, Option<PermissionPolicy>> = HashMap::new();
+        calls.retain(|call| {
+            let policy = policies
+                .entry(call.cwd.clone())
+                .or_insert_with(|| self.permission_policy(call.cwd.as_deref()).ok());
+            // With unreadable settings there is no telling whether the call prompted
+            let Some(policy) = policy else {
+                return false;
+            };
+            let mode = call
+                .permission_mode
+                .as_deref()
+                .unwrap_or(policy.default_mode());
+            let simulation = policy.evaluate_in_mode(&call.as_call(), mode);
+            simulation.decision == PermissionDecision::Ask && simulation.decided_by.is_none()
+        });
+
+        Ok(permission_suggestions::suggest_permission_rules(
+            &calls, limit,
+        ))
+    }
+
+    /// Add `rule` to the allow list of a settings file, leaving other keys untouched
+    ///
+    /// `file_path` is a `settings*.json` directly in `~/.claude` or in a known
+    /// project's `.claude` directory, such as the paths the settings layers list.
+    pub async fn apply_permission_suggestion(
+        &self,
+        file_path: &str,
+        rule: &str,
+    ) -> ManagerResult<()> {
+        let file_path = self.claude_file_path(file_path).await?;
+        let is_settings_file = file_path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| name.starts_with("settings.") && name.ends_with(".json"));
+        let in_settings_dir = file_path.parent().is_some_and(|parent| {
+            parent.file_name().is_some_and(|name| name == ".claude")
+                || fs::canonicalize(&self.claude_dir).is_ok_and(|claude_dir| claude_dir == parent)
+        });
+        if !is_settings_file || !in_settings_dir {
+            return Err(ManagerError::validation(format!(
+                "Not a settings file: {}",
+                file_path.display()
+            ))
+            .with_path(&file_path));
+        }
+        if PermissionRule::parse(rule).is_none() {
+            return Err(ManagerError::validation(format!(
+                "Invalid permission rule: {rule}"
+            )));
+        }
+
+        let mut settings = if file_path.exists() {
+            serde_json::from_str(&fs::read_to_string(&file_path).at_path(&file_path)?)
+                .at_path(&file_path)?
+        } else {
+            serde_json::json!({})
+        };
+        if permission_suggestions::merge_allow_rule(&mut settings, rule)? {
+            let content = serde_json::to_string_pretty(&settings)? + "\n";
+            self.file_store.write(&file_path, &content, None)?;
+        }
         Ok(())
     }
 
+    /// Settings files that apply in `project_path`, read from the highest precedence down
+    fn settings_layers(
+        &self,
+        project_path: Option<&str>,
+    ) -> Vec<(SettingsLayer, Option<serde_json::Value>)> {
+        settings_layers::load_layers(settings_layers::layer_paths(
+            self.managed_settings_path.as_deref(),
+            &self.claude_dir,
+            project_path.map(Path::new),
+        ))
+    }
+
+    /// Settings in effect for a project after merging every layer, and where each value comes from
+    pub async fn get_effective_settings(
+        &self,
+        project_path: Option<&str>,
+    ) -> ManagerResult<EffectiveSettings> {
+        let layers = self.settings_layers(project_path);
+        let loaded: Vec<(SettingsLayer, serde_json::Value)> = layers
+            .iter()
+            .filter_map(|(layer, value)| Some((layer.clone(), value.clone()?)))
+            .collect();
+        let merged = settings_layers::merge_layers(&loaded);
+
+        Ok(EffectiveSettings {
+            settings: serde_json::from_value(merged.settings)
+                .map_err(|e| ManagerError::parse(format!("Merged settings are invalid: {e}")))?,
+            layers: layers.into_iter().map(|(layer, _)| layer).collect(),
+            origins: merged.origins,
+            overridden: merged.overridden,
+        })
+    }
+
+    /// Permission rules that apply in `cwd`, from every settings layer
+    fn permission_policy(&self, cwd: Option<&str>) -> ManagerResult<PermissionPolicy> {
+        let mut policy = PermissionPolicy::new();
+        for (layer, settings) in self.settings_layers(cwd) {
+            if let Some(error) = layer.error {
+                return Err(ManagerError::parse(error).with_path(Path::new(&layer.path)));
+            }
+            let Some(permissions) = settings.as_ref().and_then(|s| s.get("permissions")) else {
+                continue;
+            };
+            let parsed: PermissionSettings =
+                serde_json::from_value(permissions.clone()).map_err(|e| {
+                    ManagerError::parse(format!("Invalid permissions: {e}"))
+                        .with_path(Path::new(&layer.path))
+                })?;
+            policy.add_layer(
+                &layer.path,
+                &parsed,
+                permissions.get("defaultMode").is_some(),
+            );
+        }
+        Ok(policy)
+    }
+
+    /// Whether a tool call would be allowed, denied or prompted for, and which rule decided
+    pub async fn simulate_permission(
+        &self,
+        tool_name: &str,
+        input: &serde_json::Value,
+        cwd: Option<&str>,
+    ) -> ManagerResult<PermissionSimulation> {
+        let policy = self.permission_policy(cwd)?;
+        Ok(policy.evaluate(&ToolCall {
+            tool_name,
+            input,
+            cwd,
+        }))
+    }
+
+    /// Run hooks against a sample event, optionally replaying a tool call from a session
+    pub async fn test_hook(&self, request: HookTestRequest) -> ManagerResult<HookTestResult> {
+        let mut request = request;
+        let mut transcript_path = String::new();
+        let mut recorded_cwd = None;
+
+        if let Some(session_id) = request.session_id.clone() {
+            let session_file = self.find_session_file(&session_id)?;
+            transcript_path = session_file.to_string_lossy().to_string();
+
+            if let Some(tool_use_id) = request.tool_use_id.as_deref() {
+                let content = fs::read_to_string(&session_file).at_path(&session_file)?;
+                let recorded = hook_runner::find_recorded_tool_call(content.lines(), tool_use_id)
+                    .ok_or_else(|| {
+                    ManagerError::not_found(format!(
+                        "Tool call {tool_use_id} not found in {session_id}"
+                    ))
+                    .with_session(&session_id)
+                })?;
+                request.tool_name.get_or_insert(recorded.tool_name);
+                request.tool_input.get_or_insert(recorded.tool_input);
+                if let Some(response) = recorded.tool_response {
+                    request.tool_response.get_or_insert(response);
+                }
+                recorded_cwd = recorded.cwd;
+            }
+        }
+
+        let cwd = request
+            .cwd
+            .clone()
+            .or(recorded_cwd)
+            .or_else(|| home_dir().map(|home| home.to_string_lossy().to_string()))
+            .unwrap_or_default();
+        hook_runner::test_hooks(&request, &transcript_path, &cwd).await
+    }
+
     /// Clear all cached data to force fresh reads from disk
-    pub async fn clear_cache(&self) -> Result<(), Box<dyn std::error::Error>> {
+    pub async fn clear_cache(&self) -> ManagerResult<()> {
         // Clear the sessions cache
         {
             let mut cache = self._sessions_cache.write().await;
@@ -1333,6 +2480,25 @@ impl ClaudeDataManager {
             timestamps.clear();
         }
 
+        // Clear the full-text search index
+        {
+            let mut index = self.search_index.write().await;
+            index.clear();
+        }
+
+        // Clear the Bash commands extracted from sessions
+        {
+            let mut commands = self.bash_commands.write().await;
+            commands.clear();
+        }
+
+        // Drop indexed summaries so every session file is parsed again
+        {
+            let mut index = self.session_index.write().await;
+            index.clear();
+            index.save()?;
+        }
+
         Ok(())
     }
 }
@@ -0,0 +1,306 @@
+use crate::models::{
+    CommandAnalytics, CommandCount, CommandFrequency, CommandLogEntry, CommandStatus,
+};
+use chrono::Local;
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+pub const DEFAULT_ANALYTICS_LIMIT: usize = 20;
+
+/// Programs whose first argument selects what they do, e.g. `git commit`
+const SUBCOMMAND_PROGRAMS: &[&str] = &[
+    "apt",
+    "brew",
+    "bun",
+    "cargo",
+    "deno",
+    "docker",
+    "docker-compose",
+    "dotnet",
+    "gh",
+    "git",
+    "go",
+    "gradle",
+    "gradlew",
+    "helm",
+    "kubectl",
+    "make",
+    "mvn",
+    "npm",
+    "npx",
+    "pip",
+    "pip3",
+    "pnpm",
+    "poetry",
+    "python",
+    "python3",
+    "rustup",
+    "swift",
+    "terraform",
+    "uv",
+    "yarn",
+];
+
+/// Programs where the script name after `run` is what identifies the command
+const RUN_SCRIPT_PROGRAMS: &[&str] = &["bun", "npm", "pnpm", "yarn", "cargo", "uv", "poetry"];
+
+/// Wrappers that run the rest of the line as the real command
+const PREFIX_PROGRAMS: &[&str] = &["sudo", "env", "time", "nohup", "nice", "command", "exec"];
+
+/// Split a shell line into words, honouring single and double quotes and backslashes
+pub fn shell_words(line: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut current = String::new();
+    let mut in_word = false;
+    let mut quote: Option<char> = None;
+    let mut chars = line.chars();
+
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some('"'), '\\') => {
+                if let Some(next) = chars.next() {
+                    current.push(next);
+                }
+            }
+            (Some(_), c) => current.push(c),
+            (None, '\'' | '"') => {
+                quote = Some(c);
+                in_word = true;
+            }
+            (None, '\\') => {
+                if let Some(next) = chars.next() {
+                    current.push(next);
+                    in_word = true;
+                }
+            }
+            (None, c) if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut current));
+                    in_word = false;
+                }
+            }
+            (None, c) => {
+                current.push(c);
+                in_word = true;
+            }
+        }
+    }
+    if in_word {
+        words.push(current);
+    }
+    words
+}
+
+/// Split a command line on `&&`, `||`, `;`, `|` and newlines outside quotes
+pub fn command_segments(command: &str) -> Vec<String> {
+    let mut segments = Vec::new();
+    let mut current = String::new();
+    let mut quote: Option<char> = None;
+    let mut chars = command.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match quote {
+            Some(q) => {
+                if c == q {
+                    quote = None;
+                } else if c == '\\' && q == '"' {
+                    current.push(c);
+                    if let Some(next) = chars.next() {
+                        current.push(next);
+                    }
+                    continue;
+                }
+                current.push(c);
+            }
+            None => match c {
+                '\'' | '"' => {
+                    quote = Some(c);
+                    current.push(c);
+                }
+                '\\' => {
+                    current.push(c);
+                    if let Some(next) = chars.next() {
+                        current.push(next);
+                    }
+                }
+                ';' | '\n' | '|' | '&' => {
+                    // `&&`, `||` and `|` chain commands, a lone `&` backgrounds one,
+                    // and `2>&1` / `&>` are redirections
+                    if (c == '&' || c == '|') && chars.peek() == Some(&c) {
+                        chars.next();
+                    } else if c == '&' && (current.ends_with('>') || chars.peek() == Some(&'>')) {
+                        current.push(c);
+                        continue;
+                    }
+                    segments.push(std::mem::take(&mut current));
+                }
+                _ => current.push(c),
+            },
+        }
+    }
+    segments.push(current);
+
+    segments
+        .into_iter()
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .collect()
+}
+
+pub fn is_env_assignment(word: &str) -> bool {
+    word.split_once('=').is_some_and(|(name, _)| {
+        !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+    })
+}
+
+/// Program and subcommand of a single segment, e.g. `RUST_LOG=1 cargo test -p x` -> "cargo test"
+pub fn normalize_segment(segment: &str) -> Option<String> {
+    let words = shell_words(segment);
+    let mut words = words
+        .iter()
+        .map(String::as_str)
+        .skip_while(|word| is_env_assignment(word))
+        .peekable();
+
+    // Skip wrappers and their flags, e.g. `sudo -E`
+    let mut program = words.next()?;
+    while PREFIX_PROGRAMS.contains(&program) {
+        while words
+            .peek()
+            .is_some_and(|word| word.starts_with('-') || is_env_assignment(word))
+        {
+            words.next();
+        }
+        program = words.next()?;
+    }
+
+    let program = Path::new(program)
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or(program);
+    if !SUBCOMMAND_PROGRAMS.contains(&program) {
+        return Some(program.to_string());
+    }
+
+    // Flags may come before the subcommand, e.g. `npm --silent run build`
+    let is_argument = |word: &&str| !word.starts_with('-') && !word.contains(['/', '=', '.']);
+    let mut rest = words.skip_while(|word| word.starts_with('-'));
+    let Some(subcommand) = rest.next().filter(is_argument) else {
+        return Some(program.to_string());
+    };
+    if subcommand == "run" && RUN_SCRIPT_PROGRAMS.contains(&program) {
+        if let Some(script) = rest.find(|word| !word.starts_with('-')) {
+            return Some(format!("{program} run {script}"));
+        }
+    }
+    Some(format!("{program} {subcommand}"))
+}
+
+/// The command a line is about: the first segment that is not a `cd`
+pub fn normalize_command(command: &str) -> Option<String> {
+    let segments = command_segments(command);
+    segments
+        .iter()
+        .filter_map(|segment| normalize_segment(segment))
+        .find(|program| program != "cd" && program != "pushd")
+        .or_else(|| segments.first().and_then(|s| normalize_segment(s)))
+}
+
+fn counts_by_key(counts: HashMap<String, usize>) -> Vec<CommandCount> {
+    let mut counts: Vec<CommandCount> = counts
+        .into_iter()
+        .map(|(key, count)| CommandCount { key, count })
+        .collect();
+    counts.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.key.cmp(&b.key)));
+    counts
+}
+
+/// Aggregate the command timeline; `project_of` names the project an entry belongs to
+pub fn build_command_analytics<F>(
+    entries: &[CommandLogEntry],
+    project_of: F,
+    limit: usize,
+) -> CommandAnalytics
+where
+    F: Fn(&CommandLogEntry) -> Option<String>,
+{
+    let mut frequencies: HashMap<String, CommandFrequency> = HashMap::new();
+    let mut by_project: HashMap<String, usize> = HashMap::new();
+    let mut by_day: BTreeMap<String, usize> = BTreeMap::new();
+
+    for entry in entries {
+        if let Some(command) = normalize_command(&entry.command) {
+            let frequency =
+                frequencies
+                    .entry(command.clone())
+                    .or_insert_with(|| CommandFrequency {
+                        command,
+                        count: 0,
+                        completed: 0,
+                        failures: 0,
+                        failure_rate: None,
+                        last_run: None,
+                    });
+            frequency.count += 1;
+            match entry.status {
+                Some(CommandStatus::Succeeded) => frequency.completed += 1,
+                Some(CommandStatus::Failed) => {
+                    frequency.completed += 1;
+                    frequency.failures += 1;
+                }
+                _ => {}
+            }
+            frequency.last_run = frequency.last_run.max(entry.timestamp);
+        }
+
+        let project = project_of(entry).unwrap_or_else(|| "(unknown)".to_string());
+        *by_project.entry(project).or_default() += 1;
+
+        if let Some(timestamp) = entry.timestamp {
+            let day = timestamp
+                .with_timezone(&Local)
+                .format("%Y-%m-%d")
+                .to_string();
+            *by_day.entry(day).or_default() += 1;
+        }
+    }
+
+    let mut top_commands: Vec<CommandFrequency> = frequencies
+        .into_values()
+        .map(|mut frequency| {
+            frequency.failure_rate = (frequency.completed > 0)
+                .then(|| frequency.failures as f64 / frequency.completed as f64);
+            frequency
+        })
+        .collect();
+    top_commands.sort_by(|a, b| {
+        b.count
+            .cmp(&a.count)
+            .then_with(|| a.command.cmp(&b.command))
+    });
+    top_commands.truncate(limit);
+
+    let mut by_project = counts_by_key(by_project);
+    by_project.truncate(limit);
+
+    let mut slowest: Vec<CommandLogEntry> = entries
+        .iter()
+        .filter(|entry| entry.duration_ms.is_some())
+        .cloned()
+        .collect();
+    slowest.sort_by_key(|entry| Reverse(entry.duration_ms));
+    slowest.truncate(limit);
+
+    CommandAnalytics {
+        total_commands: entries.len(),
+        top_commands,
+        by_project,
+        by_day: by_day
+            .into_iter()
+            .map(|(key, count)| CommandCount { key, count })
+            .collect(),
+        slowest,
+    }
+}
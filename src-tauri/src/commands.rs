@@ -1,130 +1,227 @@
 use crate::claude_data::ClaudeDataManager;
+use crate::command_analytics::DEFAULT_ANALYTICS_LIMIT;
+use crate::cost;
+use crate::error::ManagerError;
 use crate::models::*;
+use crate::permission_suggestions::DEFAULT_SUGGESTION_LIMIT;
+use crate::query::{Query, QueryScope};
+use crate::search_index::DEFAULT_SEARCH_LIMIT;
 use std::collections::HashMap;
+use std::path::PathBuf;
 use std::sync::Arc;
-use tauri::State;
+use tauri::{AppHandle, Emitter, Manager, State};
 
 #[tauri::command]
 pub async fn get_all_sessions(
     data_manager: State<'_, Arc<ClaudeDataManager>>,
-) -> Result<Vec<ClaudeSession>, String> {
+) -> Result<Vec<ClaudeSession>, ManagerError> {
+    data_manager.get_all_sessions().await
+}
+
+#[tauri::command]
+pub async fn get_changed_sessions(
+    data_manager: State<'_, Arc<ClaudeDataManager>>,
+) -> Result<Vec<ClaudeSession>, ManagerError> {
+    data_manager.get_changed_sessions().await
+}
+
+#[tauri::command]
+pub async fn get_session_messages(
+    session_id: String,
+    data_manager: State<'_, Arc<ClaudeDataManager>>,
+) -> Result<Vec<ClaudeMessage>, ManagerError> {
+    data_manager.get_session_messages(&session_id).await
+}
+
+#[tauri::command]
+pub async fn get_session_tree(
+    session_id: String,
+    data_manager: State<'_, Arc<ClaudeDataManager>>,
+) -> Result<SessionTree, ManagerError> {
+    data_manager.get_session_tree(&session_id).await
+}
+
+#[tauri::command]
+pub async fn get_session_diagnostics(
+    session_id: String,
+    data_manager: State<'_, Arc<ClaudeDataManager>>,
+) -> Result<SessionDiagnostics, ManagerError> {
+    data_manager.get_session_diagnostics(&session_id).await
+}
+
+#[tauri::command]
+pub async fn get_image_data(
+    session_id: String,
+    message_uuid: String,
+    block_path: Vec<usize>,
+    data_manager: State<'_, Arc<ClaudeDataManager>>,
+) -> Result<String, ManagerError> {
     data_manager
-        .get_all_sessions()
+        .get_image_data(&session_id, &message_uuid, &block_path)
         .await
-        .map_err(|e| e.to_string())
 }
 
 #[tauri::command]
-pub async fn get_changed_sessions(
+pub async fn suggest_permission_rules(
+    limit: Option<usize>,
     data_manager: State<'_, Arc<ClaudeDataManager>>,
-) -> Result<Vec<ClaudeSession>, String> {
+) -> Result<Vec<PermissionSuggestion>, ManagerError> {
     data_manager
-        .get_changed_sessions()
+        .suggest_permission_rules(limit.unwrap_or(DEFAULT_SUGGESTION_LIMIT))
         .await
-        .map_err(|e| e.to_string())
 }
 
 #[tauri::command]
-pub async fn get_session_messages(
-    session_id: String,
+pub async fn apply_permission_suggestion(
+    file_path: String,
+    rule: String,
+    data_manager: State<'_, Arc<ClaudeDataManager>>,
+) -> Result<(), ManagerError> {
+    data_manager
+        .apply_permission_suggestion(&file_path, &rule)
+        .await
+}
+
+#[tauri::command]
+pub async fn simulate_permission(
+    tool_name: String,
+    input: serde_json::Value,
+    cwd: Option<String>,
     data_manager: State<'_, Arc<ClaudeDataManager>>,
-) -> Result<Vec<ClaudeMessage>, String> {
+) -> Result<PermissionSimulation, ManagerError> {
     data_manager
-        .get_session_messages(&session_id)
+        .simulate_permission(&tool_name, &input, cwd.as_deref())
         .await
-        .map_err(|e| e.to_string())
 }
 
 #[tauri::command]
-pub async fn clear_cache(data_manager: State<'_, Arc<ClaudeDataManager>>) -> Result<(), String> {
-    data_manager.clear_cache().await.map_err(|e| e.to_string())
+pub async fn test_hook(
+    request: HookTestRequest,
+    data_manager: State<'_, Arc<ClaudeDataManager>>,
+) -> Result<HookTestResult, ManagerError> {
+    data_manager.test_hook(request).await
+}
+
+#[tauri::command]
+pub async fn clear_cache(
+    data_manager: State<'_, Arc<ClaudeDataManager>>,
+) -> Result<(), ManagerError> {
+    data_manager.clear_cache().await
+}
+
+#[tauri::command]
+pub async fn search_messages(
+    query: String,
+    limit: Option<usize>,
+    data_manager: State<'_, Arc<ClaudeDataManager>>,
+) -> Result<Vec<MessageSearchHit>, ManagerError> {
+    data_manager
+        .search_messages(&query, limit.unwrap_or(DEFAULT_SEARCH_LIMIT))
+        .await
 }
 
 #[tauri::command]
 pub async fn get_command_history(
     data_manager: State<'_, Arc<ClaudeDataManager>>,
-) -> Result<Vec<CommandLogEntry>, String> {
+) -> Result<Vec<CommandLogEntry>, ManagerError> {
+    data_manager.get_command_history().await
+}
+
+#[tauri::command]
+pub async fn get_command_analytics(
+    limit: Option<usize>,
+    data_manager: State<'_, Arc<ClaudeDataManager>>,
+) -> Result<CommandAnalytics, ManagerError> {
     data_manager
-        .get_command_history()
+        .get_command_analytics(limit.unwrap_or(DEFAULT_ANALYTICS_LIMIT))
         .await
-        .map_err(|e| e.to_string())
 }
 
 #[tauri::command]
 pub async fn get_todos(
     data_manager: State<'_, Arc<ClaudeDataManager>>,
-) -> Result<Vec<TodoItem>, String> {
-    data_manager.get_todos().await.map_err(|e| e.to_string())
+) -> Result<Vec<TodoItem>, ManagerError> {
+    data_manager.get_todos().await
 }
 
 #[tauri::command]
 pub async fn get_settings(
     data_manager: State<'_, Arc<ClaudeDataManager>>,
-) -> Result<ClaudeSettings, String> {
-    data_manager.get_settings().await.map_err(|e| e.to_string())
+) -> Result<ClaudeSettings, ManagerError> {
+    data_manager.get_settings().await
 }
 
 #[tauri::command]
-pub async fn get_project_summary(
+pub async fn get_effective_settings(
+    project_path: Option<String>,
     data_manager: State<'_, Arc<ClaudeDataManager>>,
-) -> Result<Vec<ProjectSummary>, String> {
+) -> Result<EffectiveSettings, ManagerError> {
     data_manager
-        .get_project_summary()
+        .get_effective_settings(project_path.as_deref())
         .await
-        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_project_summary(
+    data_manager: State<'_, Arc<ClaudeDataManager>>,
+) -> Result<Vec<ProjectSummary>, ManagerError> {
+    data_manager.get_project_summary().await
 }
 
 #[tauri::command]
 pub async fn get_session_stats(
     data_manager: State<'_, Arc<ClaudeDataManager>>,
-) -> Result<SessionStats, String> {
-    data_manager
-        .get_session_stats()
-        .await
-        .map_err(|e| e.to_string())
+) -> Result<SessionStats, ManagerError> {
+    data_manager.get_session_stats().await
+}
+
+fn price_table_path(app: &AppHandle) -> Result<PathBuf, ManagerError> {
+    app.path()
+        .app_config_dir()
+        .map(|dir| dir.join(cost::PRICE_TABLE_FILE))
+        .map_err(|e| ManagerError::internal(e.to_string()))
 }
 
 #[tauri::command]
-pub async fn search_sessions(
-    query: String,
+pub async fn get_cost_report(
+    app: AppHandle,
     data_manager: State<'_, Arc<ClaudeDataManager>>,
-) -> Result<Vec<ClaudeSession>, String> {
-    let all_sessions = data_manager
-        .get_all_sessions()
-        .await
-        .map_err(|e| e.to_string())?;
+) -> Result<CostReport, ManagerError> {
+    let price_table = cost::load_price_table(&price_table_path(&app)?)?;
 
-    let query_lower = query.to_lowercase();
-    let filtered_sessions: Vec<ClaudeSession> = all_sessions
-        .into_iter()
-        .filter(|session| {
-            session.project_path.to_lowercase().contains(&query_lower)
-                || session.session_id.to_lowercase().contains(&query_lower)
-                || session
-                    .git_branch
-                    .as_ref()
-                    .map(|b| b.to_lowercase().contains(&query_lower))
-                    .unwrap_or(false)
-        })
-        .collect();
+    data_manager.get_cost_report(&price_table).await
+}
 
-    Ok(filtered_sessions)
+#[tauri::command]
+pub async fn get_price_table(app: AppHandle) -> Result<PriceTable, ManagerError> {
+    cost::load_price_table(&price_table_path(&app)?)
+}
+
+#[tauri::command]
+pub async fn save_price_table(app: AppHandle, price_table: PriceTable) -> Result<(), ManagerError> {
+    cost::save_price_table(&price_table_path(&app)?, &price_table)
+}
+
+#[tauri::command]
+pub async fn search_sessions(
+    query: String,
+    data_manager: State<'_, Arc<ClaudeDataManager>>,
+) -> Result<Vec<ClaudeSession>, ManagerError> {
+    let query = Query::parse(&query, QueryScope::Sessions)?;
+    data_manager.search_sessions(&query).await
 }
 
 #[tauri::command]
 pub async fn search_commands(
     query: String,
     data_manager: State<'_, Arc<ClaudeDataManager>>,
-) -> Result<Vec<CommandLogEntry>, String> {
-    let all_commands = data_manager
-        .get_command_history()
-        .await
-        .map_err(|e| e.to_string())?;
+) -> Result<Vec<CommandLogEntry>, ManagerError> {
+    let query = Query::parse(&query, QueryScope::Commands)?;
+    let all_commands = data_manager.get_command_history().await?;
 
-    let query_lower = query.to_lowercase();
     let filtered_commands: Vec<CommandLogEntry> = all_commands
         .into_iter()
-        .filter(|cmd| cmd.command.to_lowercase().contains(&query_lower))
+        .filter(|cmd| query.matches(cmd))
         .collect();
 
     Ok(filtered_commands)
@@ -133,53 +230,73 @@ pub async fn search_commands(
 #[tauri::command]
 pub async fn export_session_data(
     session_id: String,
+    options: Option<ExportOptions>,
     data_manager: State<'_, Arc<ClaudeDataManager>>,
-) -> Result<String, String> {
-    let messages = data_manager
-        .get_session_messages(&session_id)
+) -> Result<SessionExport, ManagerError> {
+    data_manager
+        .export_session(&session_id, &options.unwrap_or_default())
         .await
-        .map_err(|e| e.to_string())?;
+}
 
-    serde_json::to_string_pretty(&messages).map_err(|e| e.to_string())
+#[tauri::command]
+pub async fn export_session_to_file(
+    session_id: String,
+    options: ExportOptions,
+    path: String,
+    overwrite: Option<bool>,
+    data_manager: State<'_, Arc<ClaudeDataManager>>,
+) -> Result<RedactionReport, ManagerError> {
+    data_manager
+        .export_session_to_file(
+            &session_id,
+            &options,
+            &PathBuf::from(path),
+            overwrite.unwrap_or(false),
+        )
+        .await
 }
 
 #[tauri::command]
 pub async fn activate_ide_window(
     ide_info: IdeInfo,
     data_manager: State<'_, Arc<ClaudeDataManager>>,
-) -> Result<(), String> {
-    data_manager
-        .activate_ide_window(&ide_info)
-        .await
-        .map_err(|e| e.to_string())
+) -> Result<(), ManagerError> {
+    data_manager.activate_ide_window(&ide_info).await
 }
 
-// File watcher functionality disabled - was causing real-time updates
-// #[tauri::command]
-// pub async fn start_file_watcher(...) -> Result<(), String> { ... }
+#[tauri::command]
+pub async fn start_file_watcher(
+    app: AppHandle,
+    data_manager: State<'_, Arc<ClaudeDataManager>>,
+) -> Result<(), ManagerError> {
+    // Calling this again is a no-op; the first caller's forwarding keeps running
+    data_manager.start_change_notifications(move |change| {
+        if let Err(e) = app.emit(change.kind.event_name(), change.clone()) {
+            eprintln!("Failed to emit file change event: {e}");
+        }
+    });
+    Ok(())
+}
 
 #[tauri::command]
 pub async fn open_session_file(
     session_id: String,
     data_manager: State<'_, Arc<ClaudeDataManager>>,
-) -> Result<(), String> {
+) -> Result<(), ManagerError> {
     // Get all sessions to find the one with matching ID
-    let sessions = data_manager
-        .get_all_sessions()
-        .await
-        .map_err(|e| e.to_string())?;
+    let sessions = data_manager.get_all_sessions().await?;
 
     // Find the session with the given ID
     let session = sessions
         .into_iter()
         .find(|s| s.session_id == session_id)
-        .ok_or_else(|| format!("Session with ID {session_id} not found"))?;
+        .ok_or_else(|| {
+            ManagerError::not_found(format!("Session with ID {session_id} not found"))
+                .with_session(&session_id)
+        })?;
 
     // Get the project path mapping to convert the actual path to the encoded directory name
-    let path_mapping = data_manager
-        .get_project_path_mapping()
-        .await
-        .map_err(|e| e.to_string())?;
+    let path_mapping = data_manager.get_project_path_mapping().await?;
 
     // Find the encoded directory name for this project path
     let encoded_dir_name = path_mapping
@@ -198,7 +315,8 @@ pub async fn open_session_file(
         });
 
     // Construct the file path using the encoded directory name
-    let home_dir = dirs::home_dir().ok_or_else(|| "Could not find home directory".to_string())?;
+    let home_dir =
+        dirs::home_dir().ok_or_else(|| ManagerError::not_found("Could not find home directory"))?;
 
     let file_path = home_dir
         .join(".claude")
@@ -213,7 +331,7 @@ pub async fn open_session_file(
             .arg("-R") // Reveal in Finder
             .arg(&file_path)
             .spawn()
-            .map_err(|e| format!("Failed to open file: {e}"))?;
+            .map_err(|e| ManagerError::from_io(e).with_path(&file_path))?;
     }
 
     #[cfg(target_os = "windows")]
@@ -222,7 +340,7 @@ pub async fn open_session_file(
             .arg("/select,")
             .arg(&file_path)
             .spawn()
-            .map_err(|e| format!("Failed to open file: {e}"))?;
+            .map_err(|e| ManagerError::from_io(e).with_path(&file_path))?;
     }
 
     #[cfg(target_os = "linux")]
@@ -242,7 +360,9 @@ pub async fn open_session_file(
         }
 
         if !opened {
-            return Err("Could not find a suitable file manager".to_string());
+            return Err(ManagerError::unsupported(
+                "Could not find a suitable file manager",
+            ));
         }
     }
 
@@ -252,17 +372,14 @@ pub async fn open_session_file(
 #[tauri::command]
 pub async fn get_project_path_mapping(
     data_manager: State<'_, Arc<ClaudeDataManager>>,
-) -> Result<HashMap<String, String>, String> {
-    data_manager
-        .get_project_path_mapping()
-        .await
-        .map_err(|e| e.to_string())
+) -> Result<HashMap<String, String>, ManagerError> {
+    data_manager.get_project_path_mapping().await
 }
 
 #[tauri::command]
-pub async fn get_home_directory() -> Result<String, String> {
+pub async fn get_home_directory() -> Result<String, ManagerError> {
     dirs::home_dir()
-        .ok_or_else(|| "Could not find home directory".to_string())
+        .ok_or_else(|| ManagerError::not_found("Could not find home directory"))
         .map(|path| path.to_string_lossy().to_string())
 }
 
@@ -270,97 +387,107 @@ pub async fn get_home_directory() -> Result<String, String> {
 pub async fn get_claude_directory_info(
     project_path: String,
     data_manager: State<'_, Arc<ClaudeDataManager>>,
-) -> Result<ClaudeDirectoryInfo, String> {
-    data_manager
-        .get_claude_directory_info(&project_path)
-        .await
-        .map_err(|e| e.to_string())
+) -> Result<ClaudeDirectoryInfo, ManagerError> {
+    data_manager.get_claude_directory_info(&project_path).await
 }
 
 #[tauri::command]
 pub async fn read_claude_file(
     file_path: String,
     data_manager: State<'_, Arc<ClaudeDataManager>>,
-) -> Result<String, String> {
-    data_manager
-        .read_claude_file(&file_path)
-        .await
-        .map_err(|e| e.to_string())
+) -> Result<String, ManagerError> {
+    data_manager.read_claude_file(&file_path).await
 }
 
 #[tauri::command]
 pub async fn write_claude_file(
     file_path: String,
     content: String,
+    expected_version: Option<String>,
     data_manager: State<'_, Arc<ClaudeDataManager>>,
-) -> Result<(), String> {
+) -> Result<String, ManagerError> {
     data_manager
-        .write_claude_file(&file_path, &content)
+        .write_claude_file(&file_path, &content, expected_version.as_deref())
         .await
-        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_file_version(
+    file_path: String,
+    data_manager: State<'_, Arc<ClaudeDataManager>>,
+) -> Result<Option<String>, ManagerError> {
+    data_manager.get_file_version(&file_path).await
+}
+
+#[tauri::command]
+pub async fn list_backups(
+    file_path: String,
+    data_manager: State<'_, Arc<ClaudeDataManager>>,
+) -> Result<Vec<FileBackup>, ManagerError> {
+    data_manager.list_backups(&file_path).await
+}
+
+#[tauri::command]
+pub async fn restore_backup(
+    file_path: String,
+    backup_id: String,
+    data_manager: State<'_, Arc<ClaudeDataManager>>,
+) -> Result<String, ManagerError> {
+    data_manager.restore_backup(&file_path, &backup_id).await
 }
 
 #[tauri::command]
 pub async fn get_custom_commands(
     data_manager: State<'_, Arc<ClaudeDataManager>>,
-) -> Result<Vec<CustomCommand>, String> {
-    data_manager
-        .get_custom_commands()
-        .await
-        .map_err(|e| e.to_string())
+) -> Result<Vec<CustomCommand>, ManagerError> {
+    data_manager.get_custom_commands().await
 }
 
 #[tauri::command]
 pub async fn get_agents(
     data_manager: State<'_, Arc<ClaudeDataManager>>,
-) -> Result<Vec<Agent>, String> {
-    data_manager.get_agents().await.map_err(|e| e.to_string())
+) -> Result<Vec<Agent>, ManagerError> {
+    data_manager.get_agents().await
 }
 
 #[tauri::command]
 pub async fn save_custom_command(
     name: String,
     content: String,
+    expected_version: Option<String>,
     data_manager: State<'_, Arc<ClaudeDataManager>>,
-) -> Result<(), String> {
+) -> Result<String, ManagerError> {
     data_manager
-        .save_custom_command(&name, &content)
+        .save_custom_command(&name, &content, expected_version.as_deref())
         .await
-        .map_err(|e| e.to_string())
 }
 
 #[tauri::command]
 pub async fn save_agent(
     name: String,
     content: String,
+    expected_version: Option<String>,
     data_manager: State<'_, Arc<ClaudeDataManager>>,
-) -> Result<(), String> {
+) -> Result<String, ManagerError> {
     data_manager
-        .save_agent(&name, &content)
+        .save_agent(&name, &content, expected_version.as_deref())
         .await
-        .map_err(|e| e.to_string())
 }
 
 #[tauri::command]
 pub async fn delete_custom_command(
     name: String,
     data_manager: State<'_, Arc<ClaudeDataManager>>,
-) -> Result<(), String> {
-    data_manager
-        .delete_custom_command(&name)
-        .await
-        .map_err(|e| e.to_string())
+) -> Result<(), ManagerError> {
+    data_manager.delete_custom_command(&name).await
 }
 
 #[tauri::command]
 pub async fn delete_agent(
     name: String,
     data_manager: State<'_, Arc<ClaudeDataManager>>,
-) -> Result<(), String> {
-    data_manager
-        .delete_agent(&name)
-        .await
-        .map_err(|e| e.to_string())
+) -> Result<(), ManagerError> {
+    data_manager.delete_agent(&name).await
 }
 
 #[tauri::command]
@@ -368,11 +495,10 @@ pub async fn rename_custom_command(
     old_name: String,
     new_name: String,
     data_manager: State<'_, Arc<ClaudeDataManager>>,
-) -> Result<(), String> {
+) -> Result<(), ManagerError> {
     data_manager
         .rename_custom_command(&old_name, &new_name)
         .await
-        .map_err(|e| e.to_string())
 }
 
 #[tauri::command]
@@ -380,31 +506,39 @@ pub async fn rename_agent(
     old_name: String,
     new_name: String,
     data_manager: State<'_, Arc<ClaudeDataManager>>,
-) -> Result<(), String> {
-    data_manager
-        .rename_agent(&old_name, &new_name)
-        .await
-        .map_err(|e| e.to_string())
+) -> Result<(), ManagerError> {
+    data_manager.rename_agent(&old_name, &new_name).await
 }
 
 #[tauri::command]
 pub async fn get_all_settings_files(
     data_manager: State<'_, Arc<ClaudeDataManager>>,
-) -> Result<Vec<(String, String)>, String> {
-    data_manager
-        .get_all_settings_files()
-        .await
-        .map_err(|e| e.to_string())
+) -> Result<Vec<(String, String)>, ManagerError> {
+    data_manager.get_all_settings_files().await
 }
 
 #[tauri::command]
 pub async fn save_settings_file(
     filename: String,
     content: String,
+    force: Option<bool>,
+    expected_version: Option<String>,
     data_manager: State<'_, Arc<ClaudeDataManager>>,
-) -> Result<(), String> {
+) -> Result<SettingsSaveResult, ManagerError> {
     data_manager
-        .save_settings_file(&filename, &content)
+        .save_settings_file(
+            &filename,
+            &content,
+            force.unwrap_or(false),
+            expected_version.as_deref(),
+        )
         .await
-        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn validate_settings(
+    content: String,
+    data_manager: State<'_, Arc<ClaudeDataManager>>,
+) -> Result<Vec<SettingsDiagnostic>, ManagerError> {
+    Ok(data_manager.validate_settings(&content))
 }
@@ -0,0 +1,202 @@
+use crate::models::*;
+use std::collections::HashMap;
+
+/// One JSONL entry with the fields needed to place it in the tree
+pub struct TreeEntry {
+    pub uuid: String,
+    pub parent_uuid: Option<String>,
+    pub is_sidechain: bool,
+    pub is_compact_boundary: bool,
+    pub message: Option<ClaudeMessage>,
+}
+
+/// Build the conversation DAG from entries in file order.
+/// `summary_leaves` are the `leafUuid`s of summary lines, oldest first.
+pub fn build_session_tree(
+    session_id: &str,
+    entries: Vec<TreeEntry>,
+    summary_leaves: &[String],
+) -> SessionTree {
+    let mut index: HashMap<String, usize> = HashMap::new();
+    let mut nodes: Vec<TreeNode> = Vec::new();
+
+    for entry in entries {
+        // Resumed sessions can repeat entries; the first occurrence wins
+        if entry.uuid.is_empty() || index.contains_key(&entry.uuid) {
+            continue;
+        }
+        index.insert(entry.uuid.clone(), nodes.len());
+        nodes.push(TreeNode {
+            uuid: entry.uuid,
+            parent_uuid: entry.parent_uuid,
+            children: Vec::new(),
+            is_sidechain: entry.is_sidechain,
+            is_compact_boundary: entry.is_compact_boundary,
+            on_active_path: false,
+            message: entry.message,
+        });
+    }
+
+    let parent_index = |node: &TreeNode| -> Option<usize> {
+        node.parent_uuid
+            .as_ref()
+            .and_then(|parent| index.get(parent))
+            .copied()
+    };
+
+    let mut roots = Vec::new();
+    let mut sidechain_roots = Vec::new();
+    for i in 0..nodes.len() {
+        let parent = parent_index(&nodes[i]);
+        if let Some(parent) = parent {
+            let uuid = nodes[i].uuid.clone();
+            nodes[parent].children.push(uuid);
+        }
+
+        if nodes[i].is_sidechain {
+            if parent.is_none_or(|p| !nodes[p].is_sidechain) {
+                sidechain_roots.push(i);
+            }
+        } else if parent.is_none() {
+            roots.push(nodes[i].uuid.clone());
+        }
+    }
+
+    let sidechains = sidechain_roots
+        .into_iter()
+        .map(|root| build_sidechain(&nodes, &index, root))
+        .collect();
+
+    let active_leaf = find_active_leaf(&nodes, &index, summary_leaves);
+
+    let mut active_path = Vec::new();
+    let mut current = active_leaf;
+    while let Some(i) = current {
+        nodes[i].on_active_path = true;
+        active_path.push(nodes[i].uuid.clone());
+        current = parent_index(&nodes[i]);
+    }
+    active_path.reverse();
+
+    SessionTree {
+        session_id: session_id.to_string(),
+        active_leaf: active_leaf.map(|i| nodes[i].uuid.clone()),
+        nodes,
+        roots,
+        active_path,
+        sidechains,
+    }
+}
+
+/// Main-thread children of a node, in file order
+fn main_children(nodes: &[TreeNode], index: &HashMap<String, usize>, node: usize) -> Vec<usize> {
+    let mut children: Vec<usize> = nodes[node]
+        .children
+        .iter()
+        .filter_map(|child| index.get(child).copied())
+        .filter(|&child| !nodes[child].is_sidechain)
+        .collect();
+    children.sort_unstable();
+    children
+}
+
+/// Prefer the newest leaf below the latest summary's leaf, otherwise the newest leaf overall
+fn find_active_leaf(
+    nodes: &[TreeNode],
+    index: &HashMap<String, usize>,
+    summary_leaves: &[String],
+) -> Option<usize> {
+    let summary_start = summary_leaves
+        .iter()
+        .rev()
+        .filter_map(|leaf| index.get(leaf).copied())
+        .find(|&i| !nodes[i].is_sidechain);
+
+    match summary_start {
+        Some(start) => {
+            let mut newest = start;
+            let mut stack = vec![start];
+            while let Some(node) = stack.pop() {
+                let children = main_children(nodes, index, node);
+                if children.is_empty() {
+                    newest = newest.max(node);
+                }
+                stack.extend(children);
+            }
+            Some(newest)
+        }
+        None => (0..nodes.len())
+            .rev()
+            .find(|&i| !nodes[i].is_sidechain && main_children(nodes, index, i).is_empty()),
+    }
+}
+
+fn build_sidechain(
+    nodes: &[TreeNode],
+    index: &HashMap<String, usize>,
+    root: usize,
+) -> SidechainThread {
+    let mut members = Vec::new();
+    let mut stack = vec![root];
+    while let Some(node) = stack.pop() {
+        members.push(node);
+        stack.extend(
+            nodes[node]
+                .children
+                .iter()
+                .filter_map(|child| index.get(child).copied())
+                .filter(|&child| nodes[child].is_sidechain),
+        );
+    }
+    members.sort_unstable();
+
+    let (parent_uuid, tool_use_id) = find_spawning_task(nodes, root)
+        .map(|(parent, tool_use_id)| (Some(parent), Some(tool_use_id)))
+        .unwrap_or_else(|| {
+            let parent = nodes[root]
+                .parent_uuid
+                .clone()
+                .filter(|parent| index.contains_key(parent));
+            (parent, None)
+        });
+
+    SidechainThread {
+        root_uuid: nodes[root].uuid.clone(),
+        parent_uuid,
+        tool_use_id,
+        message_uuids: members.into_iter().map(|i| nodes[i].uuid.clone()).collect(),
+    }
+}
+
+/// Sub-agent threads start with the Task prompt as their first user message,
+/// so match it against the Task tool calls on the main thread.
+fn find_spawning_task(nodes: &[TreeNode], root: usize) -> Option<(String, String)> {
+    let prompt = match &nodes[root].message {
+        Some(ClaudeMessage::User {
+            content: MessageContent::User { content, .. },
+            ..
+        }) => content.as_str(),
+        _ => return None,
+    };
+
+    nodes[..root]
+        .iter()
+        .rev()
+        .find_map(|node| match &node.message {
+            Some(ClaudeMessage::Assistant {
+                uuid,
+                content: MessageContent::Assistant { content, .. },
+                ..
+            }) if !node.is_sidechain => content.iter().find_map(|block| match block {
+                ContentBlock::ToolUse {
+                    id, name, input, ..
+                } if name == "Task"
+                    && input.get("prompt").and_then(|p| p.as_str()) == Some(prompt) =>
+                {
+                    Some((uuid.clone(), id.clone()))
+                }
+                _ => None,
+            }),
+            _ => None,
+        })
+}
@@ -0,0 +1,181 @@
+use crate::error::{ManagerResult, PathContext};
+use crate::file_store;
+use crate::models::*;
+use chrono::{DateTime, Local, Utc};
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::path::Path;
+
+/// File name of the user-editable price table in the app config directory
+pub const PRICE_TABLE_FILE: &str = "price_table.json";
+
+impl Default for PriceTable {
+    fn default() -> Self {
+        let price = |pattern: &str, input: f64, output: f64, write: f64, read: f64| ModelPrice {
+            model_pattern: pattern.to_string(),
+            input_per_mtok: input,
+            output_per_mtok: output,
+            cache_write_per_mtok: write,
+            cache_read_per_mtok: read,
+        };
+
+        // Public list prices in USD; more specific patterns come first
+        Self {
+            currency: "USD".to_string(),
+            models: vec![
+                price("opus-4-5", 5.0, 25.0, 6.25, 0.50),
+                price("opus", 15.0, 75.0, 18.75, 1.50),
+                price("sonnet", 3.0, 15.0, 3.75, 0.30),
+                price("haiku-4-5", 1.0, 5.0, 1.25, 0.10),
+                price("haiku", 0.80, 4.0, 1.0, 0.08),
+            ],
+        }
+    }
+}
+
+impl PriceTable {
+    pub fn price_for(&self, model: &str) -> Option<&ModelPrice> {
+        self.models
+            .iter()
+            .find(|price| model.contains(price.model_pattern.as_str()))
+    }
+}
+
+impl ModelPrice {
+    pub fn cost(&self, usage: &TokenUsage) -> CostBreakdown {
+        let per_token = |tokens: u64, per_mtok: f64| tokens as f64 * per_mtok / 1_000_000.0;
+
+        let mut cost = CostBreakdown {
+            input: per_token(usage.input_tokens, self.input_per_mtok),
+            output: per_token(usage.output_tokens, self.output_per_mtok),
+            cache_write: per_token(usage.cache_creation_input_tokens, self.cache_write_per_mtok),
+            cache_read: per_token(usage.cache_read_input_tokens, self.cache_read_per_mtok),
+            total: 0.0,
+        };
+        cost.total = cost.input + cost.output + cost.cache_write + cost.cache_read;
+        cost
+    }
+}
+
+impl CostBreakdown {
+    fn add(&mut self, other: &CostBreakdown) {
+        self.input += other.input;
+        self.output += other.output;
+        self.cache_write += other.cache_write;
+        self.cache_read += other.cache_read;
+        self.total += other.total;
+    }
+}
+
+/// Load the price table, writing the defaults on first use so the user can edit them
+pub fn load_price_table(path: &Path) -> ManagerResult<PriceTable> {
+    if !path.exists() {
+        let table = PriceTable::default();
+        save_price_table(path, &table)?;
+        return Ok(table);
+    }
+
+    let content = fs::read_to_string(path).at_path(path)?;
+    serde_json::from_str(&content).at_path(path)
+}
+
+pub fn save_price_table(path: &Path, table: &PriceTable) -> ManagerResult<()> {
+    file_store::write_atomic(path, serde_json::to_string_pretty(table)?.as_bytes())
+        .at_path(path)?;
+    Ok(())
+}
+
+/// Accumulates priced usage into the groupings of a `CostReport`
+pub struct CostReportBuilder<'a> {
+    price_table: &'a PriceTable,
+    total_usage: TokenUsage,
+    total_cost: CostBreakdown,
+    by_session: HashMap<String, (TokenUsage, CostBreakdown)>,
+    by_project: HashMap<String, (TokenUsage, CostBreakdown)>,
+    by_day: HashMap<String, (TokenUsage, CostBreakdown)>,
+    by_model: HashMap<String, (TokenUsage, CostBreakdown)>,
+    unpriced_models: BTreeSet<String>,
+}
+
+impl<'a> CostReportBuilder<'a> {
+    pub fn new(price_table: &'a PriceTable) -> Self {
+        Self {
+            price_table,
+            total_usage: TokenUsage::default(),
+            total_cost: CostBreakdown::default(),
+            by_session: HashMap::new(),
+            by_project: HashMap::new(),
+            by_day: HashMap::new(),
+            by_model: HashMap::new(),
+            unpriced_models: BTreeSet::new(),
+        }
+    }
+
+    /// Record the usage of a single API request
+    pub fn add(
+        &mut self,
+        session_id: &str,
+        project_path: &str,
+        timestamp: DateTime<Utc>,
+        model: &str,
+        usage: &TokenUsage,
+    ) {
+        let cost = match self.price_table.price_for(model) {
+            Some(price) => price.cost(usage),
+            None => {
+                self.unpriced_models.insert(model.to_string());
+                CostBreakdown::default()
+            }
+        };
+
+        let day = timestamp
+            .with_timezone(&Local)
+            .format("%Y-%m-%d")
+            .to_string();
+
+        self.total_usage.add(usage);
+        self.total_cost.add(&cost);
+
+        for (groups, key) in [
+            (&mut self.by_session, session_id),
+            (&mut self.by_project, project_path),
+            (&mut self.by_day, day.as_str()),
+            (&mut self.by_model, model),
+        ] {
+            let entry = groups.entry(key.to_string()).or_default();
+            entry.0.add(usage);
+            entry.1.add(&cost);
+        }
+    }
+
+    pub fn build(self) -> CostReport {
+        fn entries(groups: HashMap<String, (TokenUsage, CostBreakdown)>) -> Vec<CostEntry> {
+            groups
+                .into_iter()
+                .map(|(key, (usage, cost))| CostEntry { key, usage, cost })
+                .collect()
+        }
+
+        let by_cost = |a: &CostEntry, b: &CostEntry| b.cost.total.total_cmp(&a.cost.total);
+
+        let mut by_session = entries(self.by_session);
+        by_session.sort_by(by_cost);
+        let mut by_project = entries(self.by_project);
+        by_project.sort_by(by_cost);
+        let mut by_model = entries(self.by_model);
+        by_model.sort_by(by_cost);
+        let mut by_day = entries(self.by_day);
+        by_day.sort_by(|a, b| a.key.cmp(&b.key));
+
+        CostReport {
+            currency: self.price_table.currency.clone(),
+            total_usage: self.total_usage,
+            total_cost: self.total_cost,
+            by_session,
+            by_project,
+            by_day,
+            by_model,
+            unpriced_models: self.unpriced_models.into_iter().collect(),
+        }
+    }
+}
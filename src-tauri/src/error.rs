@@ -0,0 +1,210 @@
+use crate::query::QueryError;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::Path;
+
+/// What went wrong, plus where, for errors shown in the UI
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct ErrorDetail {
+    pub message: String,
+    /// File the error is about
+    pub path: Option<String>,
+    pub session_id: Option<String>,
+    /// 1-based line in `path`
+    pub line: Option<usize>,
+}
+
+/// Error returned by every data manager method and Tauri command
+///
+/// Serializes as `{ code, message, path, session_id, line }`. The `code` is
+/// stable so the frontend can react to it, e.g. offer to create a file on
+/// `not_found`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "code", rename_all = "snake_case")]
+pub enum ManagerError {
+    /// A file, session or item that does not exist
+    NotFound(ErrorDetail),
+    /// Refused by the OS or by the path policy
+    PermissionDenied(ErrorDetail),
+    AlreadyExists(ErrorDetail),
+    /// File content that is not valid JSON or not in the expected shape
+    Parse(ErrorDetail),
+    /// An argument the command cannot act on, like an invalid name or rule
+    Validation(ErrorDetail),
+    /// The file changed on disk since it was read
+    Conflict(ErrorDetail),
+    /// Not available on this platform
+    Unsupported(ErrorDetail),
+    /// Any other I/O failure
+    Io(ErrorDetail),
+    Internal(ErrorDetail),
+}
+
+pub type ManagerResult<T> = Result<T, ManagerError>;
+
+impl ManagerError {
+    pub fn not_found(message: impl Into<String>) -> Self {
+        Self::NotFound(Self::detail(message))
+    }
+
+    pub fn permission_denied(message: impl Into<String>) -> Self {
+        Self::PermissionDenied(Self::detail(message))
+    }
+
+    pub fn already_exists(message: impl Into<String>) -> Self {
+        Self::AlreadyExists(Self::detail(message))
+    }
+
+    pub fn parse(message: impl Into<String>) -> Self {
+        Self::Parse(Self::detail(message))
+    }
+
+    pub fn validation(message: impl Into<String>) -> Self {
+        Self::Validation(Self::detail(message))
+    }
+
+    pub fn conflict(message: impl Into<String>) -> Self {
+        Self::Conflict(Self::detail(message))
+    }
+
+    pub fn unsupported(message: impl Into<String>) -> Self {
+        Self::Unsupported(Self::detail(message))
+    }
+
+    pub fn internal(message: impl Into<String>) -> Self {
+        Self::Internal(Self::detail(message))
+    }
+
+    fn detail(message: impl Into<String>) -> ErrorDetail {
+        ErrorDetail {
+            message: message.into(),
+            ..ErrorDetail::default()
+        }
+    }
+
+    pub fn detail_ref(&self) -> &ErrorDetail {
+        match self {
+            Self::NotFound(detail)
+            | Self::PermissionDenied(detail)
+            | Self::AlreadyExists(detail)
+            | Self::Parse(detail)
+            | Self::Validation(detail)
+            | Self::Conflict(detail)
+            | Self::Unsupported(detail)
+            | Self::Io(detail)
+            | Self::Internal(detail) => detail,
+        }
+    }
+
+    fn detail_mut(&mut self) -> &mut ErrorDetail {
+        match self {
+            Self::NotFound(detail)
+            | Self::PermissionDenied(detail)
+            | Self::AlreadyExists(detail)
+            | Self::Parse(detail)
+            | Self::Validation(detail)
+            | Self::Conflict(detail)
+            | Self::Unsupported(detail)
+            | Self::Io(detail)
+            | Self::Internal(detail) => detail,
+        }
+    }
+
+    /// Attach the file the error is about, keeping one that is already set
+    pub fn with_path(mut self, path: &Path) -> Self {
+        let detail = self.detail_mut();
+        if detail.path.is_none() {
+            detail.path = Some(path.to_string_lossy().to_string());
+        }
+        self
+    }
+
+    pub fn with_session(mut self, session_id: &str) -> Self {
+        self.detail_mut().session_id = Some(session_id.to_string());
+        self
+    }
+
+    pub fn with_line(mut self, line: usize) -> Self {
+        self.detail_mut().line = Some(line);
+        self
+    }
+}
+
+impl fmt::Display for ManagerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let detail = self.detail_ref();
+        match &detail.path {
+            Some(path) if !detail.message.contains(path.as_str()) => {
+                write!(f, "{}: {}", path, detail.message)
+            }
+            _ => f.write_str(&detail.message),
+        }
+    }
+}
+
+impl std::error::Error for ManagerError {}
+
+impl ManagerError {
+    /// Error for an I/O failure that is not about any one file, like starting a process
+    ///
+    /// There is deliberately no `From<io::Error>`: errors about a file go
+    /// through `at_path` so the UI can show which file failed.
+    pub fn from_io(error: std::io::Error) -> Self {
+        let message = error.to_string();
+        match error.kind() {
+            std::io::ErrorKind::NotFound => Self::not_found(message),
+            std::io::ErrorKind::PermissionDenied => Self::permission_denied(message),
+            std::io::ErrorKind::AlreadyExists => Self::already_exists(message),
+            std::io::ErrorKind::InvalidData => Self::parse(message),
+            _ => Self::Io(Self::detail(message)),
+        }
+    }
+}
+
+impl From<serde_json::Error> for ManagerError {
+    fn from(error: serde_json::Error) -> Self {
+        let line = error.line();
+        let parse = Self::parse(error.to_string());
+        // Errors that did not come from parsing text have no position
+        if line > 0 {
+            parse.with_line(line)
+        } else {
+            parse
+        }
+    }
+}
+
+impl From<QueryError> for ManagerError {
+    fn from(error: QueryError) -> Self {
+        Self::validation(error.to_string())
+    }
+}
+
+impl From<notify::Error> for ManagerError {
+    fn from(error: notify::Error) -> Self {
+        Self::Io(Self::detail(error.to_string()))
+    }
+}
+
+/// Attach a path to the error of an I/O or parse result
+pub trait PathContext<T> {
+    fn at_path(self, path: &Path) -> ManagerResult<T>;
+}
+
+impl<T> PathContext<T> for std::io::Result<T> {
+    fn at_path(self, path: &Path) -> ManagerResult<T> {
+        self.map_err(|e| ManagerError::from_io(e).with_path(path))
+    }
+}
+
+impl<T> PathContext<T> for Result<T, serde_json::Error> {
+    fn at_path(self, path: &Path) -> ManagerResult<T> {
+        self.map_err(|e| ManagerError::from(e).with_path(path))
+    }
+}
+
+impl<T> PathContext<T> for ManagerResult<T> {
+    fn at_path(self, path: &Path) -> ManagerResult<T> {
+        self.map_err(|e| e.with_path(path))
+    }
+}
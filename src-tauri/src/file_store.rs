@@ -0,0 +1,233 @@
+use crate::error::{ManagerError, ManagerResult, PathContext};
+use crate::models::FileBackup;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use std::fs;
+use std::io::{self, ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Directory under the app data dir that holds backups of edited files
+pub const BACKUP_DIR: &str = "backups";
+
+/// Versions kept per file before the oldest backup is removed
+pub const DEFAULT_MAX_BACKUPS: usize = 10;
+
+/// Backup ids are UTC timestamps, so sorting them sorts by age
+const BACKUP_ID_FORMAT: &str = "%Y%m%dT%H%M%S%.9fZ";
+
+/// Keeps temp files of concurrent writes in this process apart
+static TEMP_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Optimistic concurrency token for file content
+///
+/// Callers pass the token they got when reading a file back with the write,
+/// which then fails if someone else changed the file in between.
+pub fn content_version(content: &[u8]) -> String {
+    format!("{:016x}-{}", fnv1a(content), content.len())
+}
+
+/// FNV-1a hash, which unlike `DefaultHasher` is the same on every build
+pub fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// Version of the file at `path`, or None if it does not exist
+pub fn file_version(path: &Path) -> io::Result<Option<String>> {
+    match fs::read(path) {
+        Ok(content) => Ok(Some(content_version(&content))),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Replace `path` with `content` so readers see either the old or the new file
+///
+/// The content goes to a temp file next to `path` first, which is then renamed
+/// over it. A crash mid-write leaves the original file untouched. When `path`
+/// is a symlink, the file it points to is replaced and the link is kept.
+pub fn write_atomic(path: &Path, content: &[u8]) -> io::Result<()> {
+    let resolved = fs::canonicalize(path).ok();
+    let path = resolved.as_deref().unwrap_or(path);
+    let parent = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| io::Error::new(ErrorKind::InvalidInput, "Path has no file name"))?;
+    fs::create_dir_all(parent)?;
+
+    let temp_path = parent.join(format!(
+        ".{}.{}-{}.tmp",
+        file_name.to_string_lossy(),
+        std::process::id(),
+        TEMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    let result = (|| {
+        let mut file = fs::File::create(&temp_path)?;
+        file.write_all(content)?;
+        file.sync_all()?;
+        if let Ok(metadata) = fs::metadata(path) {
+            fs::set_permissions(&temp_path, metadata.permissions())?;
+        }
+        fs::rename(&temp_path, path)
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+    result
+}
+
+/// Writes files atomically and keeps their previous versions
+pub struct FileStore {
+    /// None keeps no backups
+    backup_dir: Option<PathBuf>,
+    max_backups: usize,
+}
+
+impl FileStore {
+    pub fn without_backups() -> Self {
+        Self {
+            backup_dir: None,
+            max_backups: 0,
+        }
+    }
+
+    pub fn with_backups(backup_dir: PathBuf, max_backups: usize) -> Self {
+        Self {
+            backup_dir: Some(backup_dir),
+            max_backups,
+        }
+    }
+
+    /// Write `content` to `path`, backing up the version it replaces
+    ///
+    /// Fails without writing if `expected_version` is given and the file on
+    /// disk no longer has that version. Returns the version of the new content.
+    pub fn write(
+        &self,
+        path: &Path,
+        content: &str,
+        expected_version: Option<&str>,
+    ) -> ManagerResult<String> {
+        let current = match fs::read(path) {
+            Ok(current) => Some(current),
+            Err(e) if e.kind() == ErrorKind::NotFound => None,
+            Err(e) => return Err(e).at_path(path),
+        };
+
+        if let Some(expected) = expected_version {
+            if current.as_deref().map(content_version).as_deref() != Some(expected) {
+                return Err(ManagerError::conflict(format!(
+                    "{} changed on disk since it was read",
+                    path.display()
+                ))
+                .with_path(path));
+            }
+        }
+
+        if let Some(previous) = current.filter(|previous| previous != content.as_bytes()) {
+            self.back_up(path, &previous).at_path(path)?;
+        }
+        write_atomic(path, content.as_bytes()).at_path(path)?;
+        Ok(content_version(content.as_bytes()))
+    }
+
+    /// Backups of `path`, newest first
+    pub fn list_backups(&self, path: &Path) -> io::Result<Vec<FileBackup>> {
+        let Some(dir) = self.backups_of(path) else {
+            return Ok(Vec::new());
+        };
+        let entries = match fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+
+        let mut backups = Vec::new();
+        for entry in entries {
+            let entry = entry?;
+            let file_name = entry.file_name();
+            let Some(id) = file_name
+                .to_str()
+                .and_then(|name| name.strip_suffix(".bak"))
+            else {
+                continue;
+            };
+            let Ok(created_at) = NaiveDateTime::parse_from_str(id, BACKUP_ID_FORMAT) else {
+                continue;
+            };
+            backups.push(FileBackup {
+                id: id.to_string(),
+                path: entry.path().to_string_lossy().to_string(),
+                created_at: DateTime::from_naive_utc_and_offset(created_at, Utc),
+                size: entry.metadata()?.len(),
+            });
+        }
+        backups.sort_by(|a, b| b.id.cmp(&a.id));
+        Ok(backups)
+    }
+
+    /// Put backup `id` of `path` back in place
+    ///
+    /// The version being replaced is backed up itself, so a restore can be undone.
+    pub fn restore_backup(&self, path: &Path, id: &str) -> ManagerResult<String> {
+        let backup = self
+            .list_backups(path)
+            .at_path(path)?
+            .into_iter()
+            .find(|backup| backup.id == id)
+            .ok_or_else(|| {
+                ManagerError::not_found(format!("No backup {id} of {}", path.display()))
+                    .with_path(path)
+            })?;
+        let content = fs::read_to_string(&backup.path).at_path(Path::new(&backup.path))?;
+        self.write(path, &content, None)
+    }
+
+    /// Directory holding the backups of `path`
+    ///
+    /// The file name keeps it recognisable; the hash of the full path keeps
+    /// files with the same name in different projects apart. The directory part
+    /// is canonicalized so every spelling of the same file shares its backups.
+    fn backups_of(&self, path: &Path) -> Option<PathBuf> {
+        let backup_dir = self.backup_dir.as_ref()?;
+        let file_name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let directory = path
+            .parent()
+            .and_then(|parent| fs::canonicalize(parent).ok())
+            .unwrap_or_default();
+        // Backups outlive the process, so the hash must not change between builds
+        let full_path = directory.join(&file_name);
+        let hash = fnv1a(full_path.to_string_lossy().as_bytes());
+        Some(backup_dir.join(format!("{file_name}-{hash:016x}")))
+    }
+
+    fn back_up(&self, path: &Path, content: &[u8]) -> io::Result<()> {
+        let Some(dir) = self.backups_of(path) else {
+            return Ok(());
+        };
+        fs::create_dir_all(&dir)?;
+
+        let mut now = Utc::now();
+        let mut backup_path;
+        loop {
+            backup_path = dir.join(format!("{}.bak", now.format(BACKUP_ID_FORMAT)));
+            if !backup_path.exists() {
+                break;
+            }
+            now += chrono::Duration::nanoseconds(1);
+        }
+        write_atomic(&backup_path, content)?;
+
+        for old in self.list_backups(path)?.into_iter().skip(self.max_backups) {
+            fs::remove_file(&old.path)?;
+        }
+        Ok(())
+    }
+}
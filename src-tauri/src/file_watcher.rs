@@ -0,0 +1,131 @@
+use crate::models::{FileChangeEvent, FileChangeKind};
+use notify::{Event, EventKind};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Component, Path, PathBuf};
+use std::sync::mpsc::{Receiver, RecvTimeoutError};
+use std::time::{Duration, Instant};
+
+/// A batch is flushed once no new event arrived for this long
+pub const DEBOUNCE_WINDOW: Duration = Duration::from_millis(250);
+
+/// Upper bound on how long a batch is held back while events keep arriving,
+/// so a session that is being written to continuously still gets updates
+pub const MAX_BATCH_DELAY: Duration = Duration::from_secs(1);
+
+/// Map a changed path below `claude_dir` to the kind of change and the affected id
+///
+/// IDE lock files are reported as added or removed depending on whether they
+/// exist when the batch is flushed, since backends disagree on how they
+/// report creations, renames and deletions.
+pub fn classify_path(claude_dir: &Path, path: &Path) -> Option<(FileChangeKind, String)> {
+    let relative = path.strip_prefix(claude_dir).ok()?;
+    let parts: Vec<&str> = relative
+        .components()
+        .map(|c| match c {
+            Component::Normal(part) => part.to_str(),
+            _ => None,
+        })
+        .collect::<Option<_>>()?;
+
+    let stem = |name: &str, extension: &str| -> Option<String> {
+        name.strip_suffix(extension)
+            .filter(|stem| !stem.is_empty())
+            .map(str::to_string)
+    };
+
+    match parts.as_slice() {
+        ["projects", _, file] => {
+            stem(file, ".jsonl").map(|id| (FileChangeKind::SessionUpdated, id))
+        }
+        ["todos", file] => stem(file, ".json").map(|id| (FileChangeKind::TodoChanged, id)),
+        [file] if file.starts_with("settings.") && file.ends_with(".json") => {
+            Some((FileChangeKind::SettingsChanged, file.to_string()))
+        }
+        ["commands", file] => stem(file, ".md").map(|name| (FileChangeKind::CommandChanged, name)),
+        ["agents", file] => stem(file, ".md").map(|name| (FileChangeKind::AgentChanged, name)),
+        ["ide", file] => stem(file, ".lock").map(|name| {
+            if path.exists() {
+                (FileChangeKind::IdeLockAdded, name)
+            } else {
+                (FileChangeKind::IdeLockRemoved, name)
+            }
+        }),
+        _ => None,
+    }
+}
+
+/// Group changed paths into one event per kind with sorted, de-duplicated ids
+pub fn coalesce(claude_dir: &Path, paths: &BTreeSet<PathBuf>) -> Vec<FileChangeEvent> {
+    let mut grouped: BTreeMap<FileChangeKind, BTreeSet<String>> = BTreeMap::new();
+    for path in paths {
+        if let Some((kind, id)) = classify_path(claude_dir, path) {
+            grouped.entry(kind).or_default().insert(id);
+        }
+    }
+
+    grouped
+        .into_iter()
+        .map(|(kind, ids)| FileChangeEvent {
+            kind,
+            ids: ids.into_iter().collect(),
+        })
+        .collect()
+}
+
+fn collect_paths(
+    event: Event,
+    claude_dir: &Path,
+    canonical_dir: Option<&Path>,
+    paths: &mut BTreeSet<PathBuf>,
+) {
+    // Reads and closes are reported on some platforms but never change anything
+    if matches!(event.kind, EventKind::Access(_)) {
+        return;
+    }
+    for path in event.paths {
+        // notify may report canonical paths (e.g. /private/var on macOS)
+        match canonical_dir.and_then(|dir| path.strip_prefix(dir).ok()) {
+            Some(relative) => paths.insert(claude_dir.join(relative)),
+            None => paths.insert(path),
+        };
+    }
+}
+
+/// Read raw notify events until the sender is dropped, emitting coalesced batches
+pub fn run_debouncer<F>(claude_dir: PathBuf, events: Receiver<Event>, mut emit: F)
+where
+    F: FnMut(FileChangeEvent),
+{
+    let canonical_dir = claude_dir.canonicalize().ok();
+    let canonical_dir = canonical_dir.as_deref();
+
+    while let Ok(first) = events.recv() {
+        let mut paths = BTreeSet::new();
+        collect_paths(first, &claude_dir, canonical_dir, &mut paths);
+
+        let deadline = Instant::now() + MAX_BATCH_DELAY;
+        let mut disconnected = false;
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                break;
+            }
+            match events.recv_timeout(DEBOUNCE_WINDOW.min(remaining)) {
+                Ok(event) => collect_paths(event, &claude_dir, canonical_dir, &mut paths),
+                Err(RecvTimeoutError::Timeout) => break,
+                Err(RecvTimeoutError::Disconnected) => {
+                    disconnected = true;
+                    break;
+                }
+            }
+        }
+
+        for change in coalesce(&claude_dir, &paths) {
+            emit(change);
+        }
+
+        if disconnected {
+            break;
+        }
+    }
+}
@@ -0,0 +1,392 @@
+use crate::error::{ManagerError, ManagerResult};
+use crate::models::{Hook, HookRunResult, HookTestRequest, HookTestResult, HookVerdict};
+use regex::Regex;
+use std::path::Path;
+use std::process::Stdio;
+use std::time::{Duration, Instant};
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+/// Claude Code stops hooks that run longer than this unless they set `timeout`
+pub const DEFAULT_HOOK_TIMEOUT_SECS: u64 = 60;
+
+pub const HOOK_EVENTS: &[&str] = &[
+    "PreToolUse",
+    "PostToolUse",
+    "UserPromptSubmit",
+    "Notification",
+    "Stop",
+    "SubagentStop",
+    "PreCompact",
+    "SessionStart",
+    "SessionEnd",
+];
+
+/// Events where exit code 2 blocks what Claude was doing rather than only
+/// showing stderr to the user
+const BLOCKING_EVENTS: &[&str] = &[
+    "PreToolUse",
+    "PostToolUse",
+    "UserPromptSubmit",
+    "Stop",
+    "SubagentStop",
+];
+
+/// A tool call taken from a session file to use as a hook payload
+#[derive(Debug, Clone)]
+pub struct RecordedToolCall {
+    pub tool_name: String,
+    pub tool_input: serde_json::Value,
+    pub tool_response: Option<serde_json::Value>,
+    pub cwd: Option<String>,
+}
+
+/// Find the tool_use with `tool_use_id` and its result in a session file's lines
+pub fn find_recorded_tool_call<'a>(
+    lines: impl IntoIterator<Item = &'a str>,
+    tool_use_id: &str,
+) -> Option<RecordedToolCall> {
+    let mut found: Option<RecordedToolCall> = None;
+    for line in lines {
+        let Ok(raw) = serde_json::from_str::<serde_json::Value>(line) else {
+            continue;
+        };
+        let blocks = raw
+            .get("message")
+            .and_then(|m| m.get("content"))
+            .and_then(|c| c.as_array());
+
+        for block in blocks.into_iter().flatten() {
+            let block_type = block.get("type").and_then(|t| t.as_str());
+            if block_type == Some("tool_use")
+                && block.get("id").and_then(|i| i.as_str()) == Some(tool_use_id)
+            {
+                let Some(tool_name) = block.get("name").and_then(|n| n.as_str()) else {
+                    continue;
+                };
+                found = Some(RecordedToolCall {
+                    tool_name: tool_name.to_string(),
+                    tool_input: block.get("input").cloned().unwrap_or_default(),
+                    tool_response: None,
+                    cwd: raw.get("cwd").and_then(|c| c.as_str()).map(str::to_string),
+                });
+            } else if block_type == Some("tool_result")
+                && block.get("tool_use_id").and_then(|i| i.as_str()) == Some(tool_use_id)
+            {
+                let call = found.as_mut()?;
+                // The structured result is richer than the text shown to the model
+                call.tool_response = raw
+                    .get("toolUseResult")
+                    .or_else(|| block.get("content"))
+                    .cloned();
+                return found;
+            }
+        }
+    }
+    found
+}
+
+/// Whether a hook matcher selects `tool_name`
+///
+/// Matchers are regexes over the whole tool name, so `Edit|Write` is two
+/// alternatives and `mcp__.*` selects every MCP tool; an empty matcher or `*`
+/// matches every tool.
+pub fn matcher_matches(matcher: &str, tool_name: &str) -> ManagerResult<bool> {
+    let matcher = matcher.trim();
+    if matcher.is_empty() || matcher == "*" {
+        return Ok(true);
+    }
+    let regex = Regex::new(&format!("^(?:{matcher})$"))
+        .map_err(|e| ManagerError::validation(format!("Invalid hook matcher {matcher:?}: {e}")))?;
+    Ok(regex.is_match(tool_name))
+}
+
+/// The JSON Claude Code writes to a hook's stdin for `request.event`
+pub fn build_payload(
+    request: &HookTestRequest,
+    transcript_path: &str,
+    cwd: &str,
+) -> serde_json::Value {
+    let mut payload = serde_json::json!({
+        "session_id": request.session_id.clone().unwrap_or_else(|| "hook-test".to_string()),
+        "transcript_path": transcript_path,
+        "cwd": cwd,
+        "hook_event_name": request.event,
+    });
+    let fields = payload.as_object_mut().expect("payload is an object");
+    let tool_name = request.tool_name.clone().unwrap_or_default();
+    let tool_input = request
+        .tool_input
+        .clone()
+        .unwrap_or_else(|| serde_json::json!({}));
+
+    match request.event.as_str() {
+        "PreToolUse" => {
+            fields.insert("tool_name".into(), tool_name.into());
+            fields.insert("tool_input".into(), tool_input);
+        }
+        "PostToolUse" => {
+            fields.insert("tool_name".into(), tool_name.into());
+            fields.insert("tool_input".into(), tool_input);
+            fields.insert(
+                "tool_response".into(),
+                request.tool_response.clone().unwrap_or_default(),
+            );
+        }
+        "UserPromptSubmit" => {
+            fields.insert(
+                "prompt".into(),
+                request.prompt.clone().unwrap_or_default().into(),
+            );
+        }
+        "Notification" => {
+            let message = request
+                .prompt
+                .clone()
+                .unwrap_or_else(|| "Claude needs your permission to use a tool".to_string());
+            fields.insert("message".into(), message.into());
+        }
+        "Stop" | "SubagentStop" => {
+            fields.insert("stop_hook_active".into(), false.into());
+        }
+        "PreCompact" => {
+            fields.insert("trigger".into(), "manual".into());
+            fields.insert(
+                "custom_instructions".into(),
+                request.prompt.clone().unwrap_or_default().into(),
+            );
+        }
+        "SessionStart" => {
+            fields.insert("source".into(), "startup".into());
+        }
+        "SessionEnd" => {
+            fields.insert("reason".into(), "other".into());
+        }
+        _ => {}
+    }
+    payload
+}
+
+/// How Claude Code reads a finished hook run
+#[derive(Debug, Clone, PartialEq)]
+pub struct HookInterpretation {
+    pub verdict: HookVerdict,
+    /// Shown to Claude when blocking, otherwise to the user
+    pub reason: Option<String>,
+    pub additional_context: Option<String>,
+    pub json_output: Option<serde_json::Value>,
+}
+
+impl HookInterpretation {
+    fn new(verdict: HookVerdict, reason: Option<String>) -> Self {
+        Self {
+            verdict,
+            reason,
+            additional_context: None,
+            json_output: None,
+        }
+    }
+}
+
+/// What Claude Code would do after a hook exits with `exit_code` and prints
+/// `stdout` and `stderr`
+///
+/// Exit code 2 blocks, other non-zero codes are reported but ignored, and on
+/// success a JSON object on stdout can approve, block, ask, stop the session
+/// or add context.
+pub fn interpret_output(
+    event: &str,
+    exit_code: Option<i32>,
+    stdout: &str,
+    stderr: &str,
+) -> HookInterpretation {
+    let stderr = stderr.trim();
+    let stderr_text = (!stderr.is_empty()).then(|| stderr.to_string());
+    match exit_code {
+        Some(0) => {}
+        Some(2) if BLOCKING_EVENTS.contains(&event) => {
+            return HookInterpretation::new(HookVerdict::Block, stderr_text)
+        }
+        _ => return HookInterpretation::new(HookVerdict::NonBlockingError, stderr_text),
+    }
+
+    let stdout = stdout.trim();
+    let json = serde_json::from_str::<serde_json::Value>(stdout)
+        .ok()
+        .filter(|value| value.is_object());
+    let Some(output) = json else {
+        // Plain stdout becomes context only for these events
+        let adds_context = matches!(event, "UserPromptSubmit" | "SessionStart");
+        if adds_context && !stdout.is_empty() {
+            return HookInterpretation {
+                additional_context: Some(stdout.to_string()),
+                ..HookInterpretation::new(HookVerdict::AddContext, None)
+            };
+        }
+        return HookInterpretation::new(HookVerdict::Proceed, None);
+    };
+
+    let text =
+        |value: Option<&serde_json::Value>| value.and_then(|v| v.as_str()).map(str::to_string);
+    let specific = output.get("hookSpecificOutput");
+    let context = text(specific.and_then(|s| s.get("additionalContext")));
+
+    if output.get("continue").and_then(|c| c.as_bool()) == Some(false) {
+        return HookInterpretation {
+            verdict: HookVerdict::StopSession,
+            reason: text(output.get("stopReason")),
+            additional_context: context,
+            json_output: Some(output),
+        };
+    }
+
+    let permission_decision = specific
+        .and_then(|s| s.get("permissionDecision"))
+        .and_then(|d| d.as_str());
+    let (verdict, reason) = match (event, permission_decision) {
+        ("PreToolUse", Some(decision)) => {
+            let reason = text(specific.and_then(|s| s.get("permissionDecisionReason")));
+            match decision {
+                "allow" => (HookVerdict::Approve, reason),
+                "deny" => (HookVerdict::Block, reason),
+                "ask" => (HookVerdict::Ask, reason),
+                _ => (HookVerdict::Proceed, None),
+            }
+        }
+        _ => match output.get("decision").and_then(|d| d.as_str()) {
+            Some("block") => (HookVerdict::Block, text(output.get("reason"))),
+            // Older hooks approve with a top-level decision
+            Some("approve") if event == "PreToolUse" => {
+                (HookVerdict::Approve, text(output.get("reason")))
+            }
+            _ if context.is_some() => (HookVerdict::AddContext, None),
+            _ => (HookVerdict::Proceed, None),
+        },
+    };
+    HookInterpretation {
+        verdict,
+        reason,
+        additional_context: context,
+        json_output: Some(output),
+    }
+}
+
+/// Run one command hook with `payload` on stdin, killing it after its timeout
+pub async fn run_hook(
+    event: &str,
+    hook: &Hook,
+    payload: &serde_json::Value,
+    cwd: &str,
+) -> ManagerResult<HookRunResult> {
+    if hook.hook_type != "command" || hook.command.trim().is_empty() {
+        return Ok(HookRunResult {
+            command: hook.command.clone(),
+            verdict: HookVerdict::Skipped,
+            reason: Some(format!(
+                "Only hooks of type `command` with a command can be run, not `{}`",
+                hook.hook_type
+            )),
+            ..HookRunResult::default()
+        });
+    }
+
+    let mut command = if cfg!(windows) {
+        let mut command = Command::new("cmd");
+        command.arg("/C").arg(&hook.command);
+        command
+    } else {
+        let mut command = Command::new("sh");
+        command.arg("-c").arg(&hook.command);
+        command
+    };
+    if Path::new(cwd).is_dir() {
+        command.current_dir(cwd);
+    }
+    let mut child = command
+        .env("CLAUDE_PROJECT_DIR", cwd)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(ManagerError::from_io)?;
+
+    let input = serde_json::to_vec(payload)?;
+    let mut stdin = child.stdin.take();
+    let timeout = Duration::from_secs(hook.timeout.unwrap_or(DEFAULT_HOOK_TIMEOUT_SECS));
+    let started = Instant::now();
+    // Dropping the child when the timeout fires kills the hook
+    let output = tokio::time::timeout(timeout, async move {
+        if let Some(stdin) = stdin.as_mut() {
+            // Hooks may exit without reading their input
+            let _ = stdin.write_all(&input).await;
+        }
+        drop(stdin);
+        child.wait_with_output().await
+    })
+    .await;
+    let duration_ms = started.elapsed().as_millis() as u64;
+
+    let Ok(output) = output else {
+        return Ok(HookRunResult {
+            command: hook.command.clone(),
+            duration_ms,
+            timed_out: true,
+            verdict: HookVerdict::NonBlockingError,
+            reason: Some(format!("Timed out after {} seconds", timeout.as_secs())),
+            ..HookRunResult::default()
+        });
+    };
+    let output = output.map_err(ManagerError::from_io)?;
+
+    let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+    let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+    let exit_code = output.status.code();
+    let interpretation = interpret_output(event, exit_code, &stdout, &stderr);
+
+    Ok(HookRunResult {
+        command: hook.command.clone(),
+        exit_code,
+        stdout,
+        stderr,
+        duration_ms,
+        timed_out: false,
+        json_output: interpretation.json_output,
+        verdict: interpretation.verdict,
+        reason: interpretation.reason,
+        additional_context: interpretation.additional_context,
+    })
+}
+
+/// Build the payload for `request` and run each of its hooks in turn
+pub async fn test_hooks(
+    request: &HookTestRequest,
+    transcript_path: &str,
+    cwd: &str,
+) -> ManagerResult<HookTestResult> {
+    if !HOOK_EVENTS.contains(&request.event.as_str()) {
+        return Err(ManagerError::validation(format!(
+            "Unknown hook event: {}",
+            request.event
+        )));
+    }
+
+    let payload = build_payload(request, transcript_path, cwd);
+    let matched = match (request.matcher.as_deref(), request.tool_name.as_deref()) {
+        (Some(matcher), Some(tool_name)) => matcher_matches(matcher, tool_name)?,
+        _ => true,
+    };
+
+    let mut runs = Vec::new();
+    if matched {
+        for hook in &request.hooks {
+            runs.push(run_hook(&request.event, hook, &payload, cwd).await?);
+        }
+    }
+
+    Ok(HookTestResult {
+        payload,
+        matched,
+        runs,
+    })
+}
@@ -0,0 +1,127 @@
+use crate::file_store::fnv1a;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+
+/// Number of bytes before the cursor used to detect rewritten files
+const FINGERPRINT_BYTES: u64 = 64;
+
+/// Position after the last fully parsed line of a JSONL file
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TailCursor {
+    pub offset: u64,
+    pub line_count: usize,
+    fingerprint: u64,
+    /// The last line was complete JSON without its trailing newline yet
+    #[serde(default)]
+    open_line: bool,
+}
+
+pub struct TailLine {
+    /// 1-based line number in the file
+    pub number: usize,
+    /// Byte offset of the start of the line
+    pub offset: u64,
+    pub text: String,
+}
+
+pub struct TailRead {
+    pub lines: Vec<TailLine>,
+    pub cursor: TailCursor,
+    /// True when parsing restarted from byte 0, so earlier results must be discarded
+    pub reset: bool,
+}
+
+/// Read the lines appended since `cursor`, or the whole file when it shrank or was rewritten.
+/// A trailing line that is not valid JSON yet is left for the next read.
+pub fn read_appended_lines(path: &Path, cursor: Option<&TailCursor>) -> io::Result<TailRead> {
+    let mut file = File::open(path)?;
+    let size = file.metadata()?.len();
+
+    let resume = match cursor {
+        Some(cursor) if cursor.offset <= size => {
+            fingerprint(&mut file, cursor.offset)? == cursor.fingerprint
+        }
+        _ => false,
+    };
+    let (start, start_line, mut open_line) = match cursor {
+        Some(cursor) if resume => (cursor.offset, cursor.line_count, cursor.open_line),
+        _ => (0, 0, false),
+    };
+
+    file.seek(SeekFrom::Start(start))?;
+    let mut reader = BufReader::new(file);
+    let mut lines = Vec::new();
+    let mut offset = start;
+    let mut line_count = start_line;
+    let mut buf = Vec::new();
+
+    loop {
+        buf.clear();
+        let read = reader.read_until(b'\n', &mut buf)?;
+        if read == 0 {
+            break;
+        }
+
+        let complete = buf.ends_with(b"\n");
+        let text = String::from_utf8_lossy(&buf)
+            .trim_end_matches(['\n', '\r'])
+            .to_string();
+
+        // The newline that terminates a line already counted on the previous read
+        if std::mem::take(&mut open_line) && complete && text.is_empty() {
+            offset += read as u64;
+            continue;
+        }
+
+        // The writer may still be in the middle of this line
+        if !complete && serde_json::from_str::<serde::de::IgnoredAny>(&text).is_err() {
+            break;
+        }
+
+        open_line = !complete;
+        line_count += 1;
+        lines.push(TailLine {
+            number: line_count,
+            offset,
+            text,
+        });
+        offset += read as u64;
+    }
+
+    let mut file = reader.into_inner();
+    let cursor = TailCursor {
+        offset,
+        line_count,
+        fingerprint: fingerprint(&mut file, offset)?,
+        open_line,
+    };
+
+    Ok(TailRead {
+        lines,
+        cursor,
+        reset: !resume,
+    })
+}
+
+/// The line starting at byte `offset`, without its line break
+pub fn read_line_at(path: &Path, offset: u64) -> io::Result<String> {
+    let mut file = File::open(path)?;
+    file.seek(SeekFrom::Start(offset))?;
+    let mut buf = Vec::new();
+    BufReader::new(file).read_until(b'\n', &mut buf)?;
+    Ok(String::from_utf8_lossy(&buf)
+        .trim_end_matches(['\n', '\r'])
+        .to_string())
+}
+
+/// FNV-1a hash of the bytes just before `offset`
+fn fingerprint(file: &mut File, offset: u64) -> io::Result<u64> {
+    let start = offset.saturating_sub(FINGERPRINT_BYTES);
+    let mut bytes = Vec::new();
+    file.seek(SeekFrom::Start(start))?;
+    file.by_ref().take(offset - start).read_to_end(&mut bytes)?;
+
+    Ok(fnv1a(&bytes))
+}
@@ -1,36 +1,83 @@
 use std::sync::Arc;
 
+mod bash_history;
 mod claude_data;
+mod command_analytics;
 mod commands;
+mod conversation_tree;
+mod cost;
+mod error;
+mod file_store;
+mod file_watcher;
+mod hook_runner;
+mod jsonl_tail;
+mod log_timestamp;
 mod models;
+mod path_policy;
+mod permission_suggestions;
+mod permissions;
+mod query;
+mod redaction;
+mod search_index;
+mod session_diagnostics;
+mod session_index;
+mod settings_layers;
+mod settings_validation;
 #[cfg(test)]
 mod tests;
+mod transcript;
 
 use claude_data::ClaudeDataManager;
 use commands::*;
+use tauri::Manager;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
-    let data_manager =
-        Arc::new(ClaudeDataManager::new().expect("Failed to initialize Claude data manager"));
-
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
-        .manage(data_manager)
+        .setup(|app| {
+            let data_dir = app.path().app_data_dir()?;
+            let data_manager = Arc::new(
+                ClaudeDataManager::new()
+                    .expect("Failed to initialize Claude data manager")
+                    .with_session_index(data_dir.join(session_index::SESSION_INDEX_FILE))
+                    .with_backups(
+                        data_dir.join(file_store::BACKUP_DIR),
+                        file_store::DEFAULT_MAX_BACKUPS,
+                    ),
+            );
+            app.manage(data_manager);
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             get_all_sessions,
             get_changed_sessions,
             get_session_messages,
+            get_session_tree,
+            get_session_diagnostics,
+            get_image_data,
+            suggest_permission_rules,
+            apply_permission_suggestion,
+            simulate_permission,
+            test_hook,
             clear_cache,
             get_command_history,
+            get_command_analytics,
             get_todos,
             get_settings,
+            get_effective_settings,
             get_project_summary,
             get_session_stats,
+            get_cost_report,
+            get_price_table,
+            save_price_table,
             search_sessions,
+            search_messages,
             search_commands,
             export_session_data,
+            export_session_to_file,
             activate_ide_window,
+            start_file_watcher,
             open_session_file,
             get_project_path_mapping,
             get_home_directory,
@@ -46,7 +93,11 @@ pub fn run() {
             rename_custom_command,
             rename_agent,
             get_all_settings_files,
-            save_settings_file
+            save_settings_file,
+            validate_settings,
+            get_file_version,
+            list_backups,
+            restore_backup
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -0,0 +1,288 @@
+use chrono::{
+    DateTime, Datelike, FixedOffset, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone,
+    Timelike, Utc,
+};
+
+/// Time zone abbreviations printed by `date`, in seconds east of UTC
+///
+/// Ambiguous abbreviations use their most common meaning (CST is US Central,
+/// IST is India). Unknown ones make the timestamp unparseable rather than guessed.
+const ZONE_ABBREVIATIONS: &[(&str, i32)] = &[
+    ("UTC", 0),
+    ("GMT", 0),
+    ("UT", 0),
+    ("Z", 0),
+    ("WET", 0),
+    ("WEST", 3600),
+    ("BST", 3600),
+    ("CET", 3600),
+    ("CEST", 2 * 3600),
+    ("EET", 2 * 3600),
+    ("EEST", 3 * 3600),
+    ("MSK", 3 * 3600),
+    ("IST", 5 * 3600 + 1800),
+    ("ICT", 7 * 3600),
+    ("WIB", 7 * 3600),
+    ("CST", -6 * 3600),
+    ("HKT", 8 * 3600),
+    ("SGT", 8 * 3600),
+    ("AWST", 8 * 3600),
+    ("PHT", 8 * 3600),
+    ("JST", 9 * 3600),
+    ("KST", 9 * 3600),
+    ("ACST", 9 * 3600 + 1800),
+    ("ACDT", 10 * 3600 + 1800),
+    ("AEST", 10 * 3600),
+    ("AEDT", 11 * 3600),
+    ("NZST", 12 * 3600),
+    ("NZDT", 13 * 3600),
+    ("HST", -10 * 3600),
+    ("AKST", -9 * 3600),
+    ("AKDT", -8 * 3600),
+    ("PST", -8 * 3600),
+    ("PDT", -7 * 3600),
+    ("MST", -7 * 3600),
+    ("MDT", -6 * 3600),
+    ("CDT", -5 * 3600),
+    ("EST", -5 * 3600),
+    ("EDT", -4 * 3600),
+    ("AST", -4 * 3600),
+    ("ADT", -3 * 3600),
+    ("NST", -3 * 3600 - 1800),
+    ("NDT", -2 * 3600 - 1800),
+    ("BRT", -3 * 3600),
+    ("ART", -3 * 3600),
+];
+
+const MONTHS: [&str; 12] = [
+    "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+
+const WEEKDAYS: [&str; 7] = ["mon", "tue", "wed", "thu", "fri", "sat", "sun"];
+
+/// Parse the bracketed timestamp of a command_history.log line
+///
+/// Accepts `date` output in the C and Japanese locales with a zone
+/// abbreviation or numeric offset, RFC 2822 style dates and ISO 8601.
+/// Timestamps without any zone are taken as local time. Returns None
+/// instead of guessing.
+pub fn parse_log_timestamp(raw: &str) -> Option<DateTime<Utc>> {
+    let raw = raw.trim();
+    if raw.is_empty() {
+        return None;
+    }
+
+    parse_iso8601(raw)
+        .or_else(|| parse_japanese_date(raw))
+        .or_else(|| parse_date_output(raw))
+}
+
+fn parse_iso8601(raw: &str) -> Option<DateTime<Utc>> {
+    if let Ok(timestamp) = DateTime::parse_from_rfc3339(raw) {
+        return Some(timestamp.with_timezone(&Utc));
+    }
+    for format in ["%Y-%m-%dT%H:%M:%S%.f%z", "%Y-%m-%d %H:%M:%S%.f%z"] {
+        if let Ok(timestamp) = DateTime::parse_from_str(raw, format) {
+            return Some(timestamp.with_timezone(&Utc));
+        }
+    }
+    for format in [
+        "%Y-%m-%dT%H:%M:%S%.f",
+        "%Y-%m-%d %H:%M:%S%.f",
+        "%Y-%m-%dT%H:%M",
+    ] {
+        if let Ok(naive) = NaiveDateTime::parse_from_str(raw, format) {
+            return resolve(naive, None);
+        }
+    }
+    None
+}
+
+/// Interpret a wall-clock time at `offset` seconds east of UTC, or in local time
+fn resolve(naive: NaiveDateTime, offset: Option<i32>) -> Option<DateTime<Utc>> {
+    match offset {
+        Some(seconds) => FixedOffset::east_opt(seconds)?
+            .from_local_datetime(&naive)
+            .single()
+            .map(|t| t.with_timezone(&Utc)),
+        None => Local
+            .from_local_datetime(&naive)
+            .earliest()
+            .map(|t| t.with_timezone(&Utc)),
+    }
+}
+
+/// `+0900`, `+09:00`, `+09`, `-0530`
+fn parse_numeric_offset(token: &str) -> Option<i32> {
+    let sign = match token.chars().next()? {
+        '+' => 1,
+        '-' => -1,
+        _ => return None,
+    };
+    let digits: String = token[1..].chars().filter(|c| *c != ':').collect();
+    if !digits.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let (hours, minutes) = match digits.len() {
+        1 | 2 => (digits.parse::<i32>().ok()?, 0),
+        4 => (
+            digits[..2].parse::<i32>().ok()?,
+            digits[2..].parse::<i32>().ok()?,
+        ),
+        _ => return None,
+    };
+    if hours > 14 || minutes > 59 {
+        return None;
+    }
+    Some(sign * (hours * 3600 + minutes * 60))
+}
+
+/// Zone abbreviation, numeric offset, or `UTC+9` style combination
+fn parse_zone(token: &str) -> Option<i32> {
+    if let Some(offset) = parse_numeric_offset(token) {
+        return Some(offset);
+    }
+
+    let upper = token.to_ascii_uppercase();
+    if let Some(split) = upper.find(['+', '-']).filter(|i| *i > 0) {
+        let base = zone_abbreviation(&upper[..split])?;
+        return Some(base + parse_numeric_offset(&upper[split..])?);
+    }
+    zone_abbreviation(&upper)
+}
+
+fn zone_abbreviation(name: &str) -> Option<i32> {
+    ZONE_ABBREVIATIONS
+        .iter()
+        .find(|(abbreviation, _)| *abbreviation == name)
+        .map(|(_, offset)| *offset)
+}
+
+fn parse_time(token: &str) -> Option<NaiveTime> {
+    NaiveTime::parse_from_str(token, "%H:%M:%S%.f")
+        .or_else(|_| NaiveTime::parse_from_str(token, "%H:%M"))
+        .ok()
+}
+
+fn month_number(token: &str) -> Option<u32> {
+    let lower = token.to_ascii_lowercase();
+    MONTHS
+        .iter()
+        .position(|month| lower.starts_with(month))
+        .map(|index| index as u32 + 1)
+}
+
+fn is_weekday(token: &str) -> bool {
+    let lower = token.to_ascii_lowercase();
+    WEEKDAYS.iter().any(|day| lower.starts_with(day))
+}
+
+/// C-locale `date` output and its common variants, e.g.
+/// `Thu Jul 17 15:18:23 JST 2025` or `Thu 17 Jul 2025 03:18:23 PM +0900`
+fn parse_date_output(raw: &str) -> Option<DateTime<Utc>> {
+    let mut year = None;
+    let mut month = None;
+    let mut day = None;
+    let mut time = None;
+    let mut pm = None;
+    let mut offset = None;
+
+    for token in raw
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|t| !t.is_empty())
+    {
+        if let Ok(date) = NaiveDate::parse_from_str(token, "%Y-%m-%d") {
+            year = Some(date.year());
+            month = Some(date.month());
+            day = Some(date.day());
+        } else if token.contains(':') && token.chars().next()?.is_ascii_digit() {
+            time = Some(parse_time(token)?);
+        } else if token.chars().all(|c| c.is_ascii_digit()) {
+            match token.len() {
+                4 if year.is_none() => year = Some(token.parse().ok()?),
+                1 | 2 if day.is_none() => day = Some(token.parse().ok()?),
+                _ => return None,
+            }
+        } else if token.eq_ignore_ascii_case("am") || token.eq_ignore_ascii_case("pm") {
+            pm = Some(token.eq_ignore_ascii_case("pm"));
+        } else if let Some(number) = month_number(token) {
+            month = Some(number);
+        } else if is_weekday(token) {
+            continue;
+        } else {
+            offset = Some(parse_zone(token)?);
+        }
+    }
+
+    let mut time = time?;
+    if let Some(pm) = pm {
+        let hour = time.hour();
+        if hour == 0 || hour > 12 {
+            return None;
+        }
+        let hour = match (pm, hour) {
+            (false, 12) => 0,
+            (true, 12) => 12,
+            (true, hour) => hour + 12,
+            (false, hour) => hour,
+        };
+        time = time.with_hour(hour)?;
+    }
+
+    let date = NaiveDate::from_ymd_opt(year?, month?, day?)?;
+    resolve(date.and_time(time), offset)
+}
+
+/// `date` in the ja_JP locale, e.g. `2025年 7月17日 木曜日 15時18分23秒 JST`
+/// or `2025年  7月 17日 木曜日 15:18:23 JST`
+fn parse_japanese_date(raw: &str) -> Option<DateTime<Utc>> {
+    if !raw.contains('年') {
+        return None;
+    }
+
+    let mut fields: Vec<(char, u32)> = Vec::new();
+    let mut time = None;
+    let mut offset = None;
+    for token in raw.split_whitespace() {
+        if token.contains(':') && token.starts_with(|c: char| c.is_ascii_digit()) {
+            time = Some(parse_time(token)?);
+            continue;
+        }
+        if token.is_ascii() {
+            offset = Some(parse_zone(token)?);
+            continue;
+        }
+
+        // Numbers followed by their unit; the 日 of a weekday like 木曜日 has none
+        let mut number = String::new();
+        for c in token.chars() {
+            if c.is_ascii_digit() {
+                number.push(c);
+                continue;
+            }
+            if !number.is_empty() {
+                if !matches!(c, '年' | '月' | '日' | '時' | '分' | '秒') {
+                    return None;
+                }
+                fields.push((c, number.parse().ok()?));
+                number.clear();
+            }
+        }
+        if !number.is_empty() {
+            return None;
+        }
+    }
+
+    let field = |unit: char| {
+        fields
+            .iter()
+            .find(|(u, _)| *u == unit)
+            .map(|(_, value)| *value)
+    };
+    let time = match time {
+        Some(time) => time,
+        None => NaiveTime::from_hms_opt(field('時')?, field('分')?, field('秒').unwrap_or(0))?,
+    };
+    let date = NaiveDate::from_ymd_opt(field('年')? as i32, field('月')?, field('日')?)?;
+    resolve(date.and_time(time), offset)
+}
@@ -1,5 +1,6 @@
 use chrono::{DateTime, Utc};
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::BTreeMap;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct IdeInfo {
@@ -22,6 +23,48 @@ pub struct ClaudeSession {
     pub ide_info: Option<IdeInfo>,
     pub is_processing: bool,
     pub file_modified_time: DateTime<Utc>,
+    /// Token totals across all assistant responses in the session
+    #[serde(default)]
+    pub token_usage: TokenUsage,
+    #[serde(default)]
+    pub health: SessionHealth,
+}
+
+/// Whether every line of a session file could be read
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SessionHealth {
+    #[default]
+    Healthy,
+    /// Valid JSON lines whose entry type this app does not know
+    Unrecognized,
+    /// Lines that are not valid JSON objects, so messages may be missing
+    Corrupted,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TokenUsage {
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cache_creation_input_tokens: u64,
+    pub cache_read_input_tokens: u64,
+}
+
+impl TokenUsage {
+    pub fn add(&mut self, other: &TokenUsage) {
+        self.input_tokens += other.input_tokens;
+        self.output_tokens += other.output_tokens;
+        self.cache_creation_input_tokens += other.cache_creation_input_tokens;
+        self.cache_read_input_tokens += other.cache_read_input_tokens;
+    }
+
+    /// Total tokens including cache reads and writes
+    pub fn total(&self) -> u64 {
+        self.input_tokens
+            + self.output_tokens
+            + self.cache_creation_input_tokens
+            + self.cache_read_input_tokens
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -58,6 +101,9 @@ pub enum ClaudeMessage {
         git_branch: Option<String>,
         processing_status: ProcessingStatus,
         stop_reason: Option<String>,
+        model: Option<String>,
+        usage: Option<TokenUsage>,
+        request_id: Option<String>,
     },
     #[serde(rename = "summary")]
     Summary {
@@ -67,19 +113,49 @@ pub enum ClaudeMessage {
     },
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize)]
 #[serde(untagged)]
 pub enum MessageContent {
     User {
         role: String,
         content: String,
     },
+    /// User turn made of content blocks, e.g. tool results sent back to the model
+    UserBlocks {
+        role: String,
+        content: Vec<ContentBlock>,
+    },
     Assistant {
         role: String,
         content: Vec<ContentBlock>,
     },
 }
 
+// Block turns of users and assistants have the same shape, so `role` picks the variant
+impl<'de> Deserialize<'de> for MessageContent {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Body {
+            Text(String),
+            Blocks(Vec<ContentBlock>),
+        }
+
+        #[derive(Deserialize)]
+        struct Raw {
+            role: String,
+            content: Body,
+        }
+
+        let Raw { role, content } = Raw::deserialize(deserializer)?;
+        Ok(match content {
+            Body::Text(content) => Self::User { role, content },
+            Body::Blocks(content) if role == "assistant" => Self::Assistant { role, content },
+            Body::Blocks(content) => Self::UserBlocks { role, content },
+        })
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type")]
 pub enum ContentBlock {
@@ -90,9 +166,57 @@ pub enum ContentBlock {
         id: String,
         name: String,
         input: serde_json::Value,
+        /// Output of this tool call, paired from the matching tool_result block
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        result: Option<ToolResult>,
+    },
+    #[serde(rename = "tool_result")]
+    ToolResult {
+        tool_use_id: String,
+        content: Vec<ContentBlock>,
+        #[serde(default)]
+        is_error: bool,
+    },
+    #[serde(rename = "thinking")]
+    Thinking {
+        thinking: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        signature: Option<String>,
+    },
+    #[serde(rename = "redacted_thinking")]
+    RedactedThinking { data: String },
+    #[serde(rename = "image")]
+    Image {
+        media_type: String,
+        source: ImageSource,
     },
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ImageSource {
+    Base64 {
+        data: String,
+    },
+    Url {
+        url: String,
+    },
+    /// Large payload left on disk; load it with `get_image_data`
+    Handle {
+        message_uuid: String,
+        block_path: Vec<usize>,
+        size: usize,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolResult {
+    pub content: Vec<ContentBlock>,
+    pub is_error: bool,
+    /// uuid of the user message that carried the tool_result
+    pub message_uuid: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TodoItem {
     pub id: String,
@@ -117,12 +241,57 @@ pub enum TodoPriority {
     High,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct CommandLogEntry {
-    pub timestamp: DateTime<Utc>,
+    /// None when `raw_timestamp` could not be parsed
+    pub timestamp: Option<DateTime<Utc>>,
+    /// Timestamp exactly as written in the log
+    #[serde(default)]
+    pub raw_timestamp: String,
     pub user: String,
     pub command: String,
     pub cwd: Option<String>,
+    #[serde(default)]
+    pub source: CommandSource,
+    /// Session whose Bash tool call ran the command
+    #[serde(default)]
+    pub session_id: Option<String>,
+    #[serde(default)]
+    pub git_branch: Option<String>,
+    /// The `description` Claude gave the Bash call
+    #[serde(default)]
+    pub description: Option<String>,
+    /// Outcome from the matching tool_result; None for log-only entries
+    #[serde(default)]
+    pub status: Option<CommandStatus>,
+    #[serde(default)]
+    pub exit_code: Option<i32>,
+    /// Time from the Bash call to its tool_result
+    #[serde(default)]
+    pub duration_ms: Option<u64>,
+}
+
+/// Where a command history entry came from
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CommandSource {
+    /// command_history.log
+    #[default]
+    HistoryLog,
+    /// A Bash tool call in a session file
+    Session,
+    /// A log line matched to a Bash tool call
+    Both,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CommandStatus {
+    Succeeded,
+    Failed,
+    Interrupted,
+    /// No tool_result yet
+    Pending,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -131,6 +300,9 @@ pub struct ClaudeSettings {
     pub permissions: PermissionSettings,
     #[serde(default)]
     pub hooks: HookSettings,
+    /// Keys this app does not edit, such as `model` or `env`, kept for writing back
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -141,6 +313,11 @@ pub struct PermissionSettings {
     pub allow: Vec<String>,
     #[serde(default)]
     pub deny: Vec<String>,
+    /// Calls matching these always prompt, even when an allow rule matches
+    #[serde(default)]
+    pub ask: Vec<String>,
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
 }
 
 impl Default for PermissionSettings {
@@ -149,6 +326,8 @@ impl Default for PermissionSettings {
             default_mode: "prompt".to_string(),
             allow: Vec::new(),
             deny: Vec::new(),
+            ask: Vec::new(),
+            extra: serde_json::Map::new(),
         }
     }
 }
@@ -157,23 +336,420 @@ fn default_mode() -> String {
     "prompt".to_string()
 }
 
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PermissionDecision {
+    Allow,
+    Deny,
+    Ask,
+}
+
+/// A rule from one of the settings files, and the list it is in
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SourcedPermissionRule {
+    pub rule: String,
+    pub list: PermissionDecision,
+    /// Settings file the rule comes from
+    pub source: String,
+}
+
+/// What would happen if a session made a tool call
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PermissionSimulation {
+    pub decision: PermissionDecision,
+    /// The rule that decided, or None when the permission mode did
+    pub decided_by: Option<SourcedPermissionRule>,
+    pub reason: String,
+    /// Every rule that matched the call, including ones that lost to `decided_by`
+    pub matched_rules: Vec<SourcedPermissionRule>,
+    /// Rules that could not be parsed and were skipped
+    pub invalid_rules: Vec<SourcedPermissionRule>,
+}
+
+/// Settings files in Claude Code's precedence order, highest first
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SettingsScope {
+    /// managed-settings.json installed by an administrator
+    Enterprise,
+    /// `<project>/.claude/settings.local.json`, not checked in
+    ProjectLocal,
+    /// `<project>/.claude/settings.json`, shared with the team
+    Project,
+    /// `~/.claude/settings.json`
+    User,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SettingsLayer {
+    pub scope: SettingsScope,
+    pub path: String,
+    pub exists: bool,
+    /// Why an existing file was skipped
+    pub error: Option<String>,
+}
+
+/// Where a setting comes from; array settings have one origin per item
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SettingOrigin {
+    /// Dotted key such as `permissions.allow` or `env.RUST_LOG`
+    pub key: String,
+    pub value: serde_json::Value,
+    pub scope: SettingsScope,
+    pub path: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EffectiveSettings {
+    pub settings: ClaudeSettings,
+    pub layers: Vec<SettingsLayer>,
+    pub origins: Vec<SettingOrigin>,
+    /// Values hidden by the same key in a layer with higher precedence
+    pub overridden: Vec<SettingOrigin>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DiagnosticSeverity {
+    /// Claude Code would fail to load the file
+    Error,
+    Warning,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SettingsDiagnosticCode {
+    InvalidJson,
+    InvalidType,
+    InvalidValue,
+    UnknownKey,
+    DeprecatedKey,
+    InvalidPermissionRule,
+    HookCommandNotFound,
+}
+
+/// A problem found in a settings file before saving it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SettingsDiagnostic {
+    pub severity: DiagnosticSeverity,
+    pub code: SettingsDiagnosticCode,
+    /// Dotted key the problem is about, e.g. `permissions.allow[2]`
+    pub key: Option<String>,
+    pub message: String,
+    /// 1-based position in the file
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SettingsSaveResult {
+    /// False when errors were found and the save was not forced
+    pub saved: bool,
+    pub diagnostics: Vec<SettingsDiagnostic>,
+    /// Version of the content on disk after a save, for the next `expected_version`
+    pub version: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SessionLineIssue {
+    InvalidJson,
+    /// Valid JSON, but not an object
+    NotAnObject,
+    MissingType,
+    UnknownType,
+    /// The last line has no newline and is not valid JSON, e.g. after an interrupted write
+    Truncated,
+}
+
+/// A line of a session file that was skipped or only partly understood
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionLineDiagnostic {
+    /// 1-based line number
+    pub line: usize,
+    /// Byte offset of the start of the line
+    pub offset: u64,
+    pub issue: SessionLineIssue,
+    pub message: String,
+    /// Start of the raw line
+    pub excerpt: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionDiagnostics {
+    pub session_id: String,
+    pub file_path: String,
+    pub line_count: usize,
+    pub health: SessionHealth,
+    pub lines: Vec<SessionLineDiagnostic>,
+    /// Each unknown `type` value and the number of lines using it
+    pub unknown_types: BTreeMap<String, usize>,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportFormat {
+    /// The parsed messages as pretty-printed JSON
+    #[default]
+    Json,
+    Markdown,
+    /// A single page with its CSS embedded
+    Html,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ExportOptions {
+    #[serde(default)]
+    pub format: ExportFormat,
+    /// Include the model's thinking blocks
+    #[serde(default)]
+    pub include_thinking: bool,
+    /// Redact the messages before rendering; None exports them as they are
+    #[serde(default)]
+    pub redaction: Option<RedactionOptions>,
+}
+
+/// Which redactions run over an export
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RedactionOptions {
+    /// API keys, tokens, AWS credentials, private keys and passwords
+    pub secrets: bool,
+    /// The home directory path, replaced by `~`
+    pub home_directory: bool,
+    pub emails: bool,
+    /// This machine's host name and hosts on internal domains like `.local`
+    pub hostnames: bool,
+    /// Extra regexes whose matches are redacted
+    pub custom_patterns: Vec<String>,
+}
+
+impl Default for RedactionOptions {
+    fn default() -> Self {
+        Self {
+            secrets: true,
+            home_directory: true,
+            emails: true,
+            hostnames: true,
+            custom_patterns: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RedactionKind {
+    Secret,
+    HomeDirectory,
+    Email,
+    Hostname,
+    Custom,
+}
+
+/// One match that was replaced in an export
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Redaction {
+    pub kind: RedactionKind,
+    /// Rule that matched, e.g. `aws_access_key` or `custom[0]`
+    pub rule: String,
+    /// Position of the message in the session
+    pub message_index: usize,
+    /// uuid of the message, or the leaf uuid of a summary
+    pub message_uuid: Option<String>,
+    /// Dotted path of the field within the message, e.g. `content[1].input.command`
+    pub field: String,
+    /// Text written in place of the match
+    pub replacement: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RedactionReport {
+    pub redactions: Vec<Redaction>,
+    /// Number of matches per rule
+    pub counts: BTreeMap<String, usize>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionExport {
+    pub content: String,
+    /// Empty when redaction was off
+    pub report: RedactionReport,
+}
+
+/// A previous version of a file the app edited
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileBackup {
+    /// Timestamp id passed to `restore_backup`
+    pub id: String,
+    pub path: String,
+    pub created_at: DateTime<Utc>,
+    pub size: u64,
+}
+
+/// A past tool call shown as evidence for a suggested rule
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolCallPreview {
+    pub session_id: String,
+    pub tool_name: String,
+    /// The command, file or URL the call worked on
+    pub summary: String,
+    pub cwd: Option<String>,
+    pub timestamp: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PermissionSuggestion {
+    /// Rule in settings syntax, e.g. `Bash(npm test:*)`
+    pub rule: String,
+    pub tool_name: String,
+    /// Approved calls the rule would have matched
+    pub count: usize,
+    pub session_count: usize,
+    pub last_used: Option<DateTime<Utc>>,
+    /// Most recent matching calls
+    pub matches: Vec<ToolCallPreview>,
+}
+
+/// Hooks by event; events without hooks are left out when written back,
+/// except PreToolUse which the settings editor always expects
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct HookSettings {
     #[serde(rename = "PreToolUse", default)]
     pub pre_tool_use: Vec<HookMatcher>,
+    #[serde(rename = "PostToolUse", default, skip_serializing_if = "Vec::is_empty")]
+    pub post_tool_use: Vec<HookMatcher>,
+    #[serde(
+        rename = "UserPromptSubmit",
+        default,
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub user_prompt_submit: Vec<HookMatcher>,
+    #[serde(
+        rename = "Notification",
+        default,
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub notification: Vec<HookMatcher>,
+    #[serde(rename = "Stop", default, skip_serializing_if = "Vec::is_empty")]
+    pub stop: Vec<HookMatcher>,
+    #[serde(
+        rename = "SubagentStop",
+        default,
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub subagent_stop: Vec<HookMatcher>,
+    #[serde(rename = "PreCompact", default, skip_serializing_if = "Vec::is_empty")]
+    pub pre_compact: Vec<HookMatcher>,
+    #[serde(
+        rename = "SessionStart",
+        default,
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub session_start: Vec<HookMatcher>,
+    #[serde(rename = "SessionEnd", default, skip_serializing_if = "Vec::is_empty")]
+    pub session_end: Vec<HookMatcher>,
+    /// Events added to Claude Code after this app was written
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HookMatcher {
+    /// Tool name pattern; events that do not involve a tool leave it out
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub matcher: String,
     pub hooks: Vec<Hook>,
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Hook {
     #[serde(rename = "type")]
     pub hook_type: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub command: String,
+    /// Seconds before Claude Code stops the hook
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<u64>,
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+/// A hook test: the hooks to run and the sample event to feed them
+///
+/// The tool call can be given directly or taken from a past session through
+/// `session_id` and `tool_use_id`; explicit fields win over recorded ones.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HookTestRequest {
+    /// Event name such as `PreToolUse` or `Stop`
+    pub event: String,
+    /// Checked against `tool_name` before running, like Claude Code does
+    #[serde(default)]
+    pub matcher: Option<String>,
+    pub hooks: Vec<Hook>,
+    #[serde(default)]
+    pub tool_name: Option<String>,
+    #[serde(default)]
+    pub tool_input: Option<serde_json::Value>,
+    #[serde(default)]
+    pub tool_response: Option<serde_json::Value>,
+    /// Prompt for UserPromptSubmit, message for Notification, instructions for PreCompact
+    #[serde(default)]
+    pub prompt: Option<String>,
+    #[serde(default)]
+    pub session_id: Option<String>,
+    #[serde(default)]
+    pub tool_use_id: Option<String>,
+    #[serde(default)]
+    pub cwd: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum HookVerdict {
+    /// No effect on what Claude does
+    #[default]
+    Proceed,
+    /// The tool call runs without asking for permission
+    Approve,
+    /// The user is asked for permission
+    Ask,
+    /// The tool call, prompt or stop is blocked and the reason goes to Claude
+    Block,
+    /// The output is added to Claude's context
+    AddContext,
+    /// `continue: false` ends the session
+    StopSession,
+    /// Failed or timed out; Claude Code shows stderr and carries on
+    NonBlockingError,
+    /// Not a command hook, so it was not run
+    Skipped,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct HookRunResult {
+    pub command: String,
+    /// None when the hook timed out or was killed by a signal
+    pub exit_code: Option<i32>,
+    pub stdout: String,
+    pub stderr: String,
+    pub duration_ms: u64,
+    pub timed_out: bool,
+    /// JSON object the hook printed on stdout
+    pub json_output: Option<serde_json::Value>,
+    pub verdict: HookVerdict,
+    pub reason: Option<String>,
+    pub additional_context: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HookTestResult {
+    /// The JSON sent to each hook on stdin
+    pub payload: serde_json::Value,
+    /// False when the matcher does not select the tool, so no hook ran
+    pub matched: bool,
+    pub runs: Vec<HookRunResult>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -222,3 +798,168 @@ pub struct Agent {
     pub name: String,
     pub content: String,
 }
+
+/// Per-model prices in currency units per million tokens
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelPrice {
+    /// Matched as a substring of the model id, e.g. "opus-4"
+    pub model_pattern: String,
+    pub input_per_mtok: f64,
+    pub output_per_mtok: f64,
+    pub cache_write_per_mtok: f64,
+    pub cache_read_per_mtok: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PriceTable {
+    pub currency: String,
+    /// Checked in order; the first matching pattern wins
+    pub models: Vec<ModelPrice>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CostBreakdown {
+    pub input: f64,
+    pub output: f64,
+    pub cache_write: f64,
+    pub cache_read: f64,
+    pub total: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CostEntry {
+    pub key: String,
+    pub usage: TokenUsage,
+    pub cost: CostBreakdown,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CostReport {
+    pub currency: String,
+    pub total_usage: TokenUsage,
+    pub total_cost: CostBreakdown,
+    pub by_session: Vec<CostEntry>,
+    pub by_project: Vec<CostEntry>,
+    pub by_day: Vec<CostEntry>,
+    pub by_model: Vec<CostEntry>,
+    /// Models seen in sessions that have no entry in the price table
+    pub unpriced_models: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TreeNode {
+    pub uuid: String,
+    pub parent_uuid: Option<String>,
+    pub children: Vec<String>,
+    pub is_sidechain: bool,
+    /// System entry written when the conversation was compacted
+    pub is_compact_boundary: bool,
+    pub on_active_path: bool,
+    /// None for entries that are not user/assistant messages, e.g. compaction boundaries
+    pub message: Option<ClaudeMessage>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SidechainThread {
+    pub root_uuid: String,
+    /// Main-thread message whose Task tool call started this thread
+    pub parent_uuid: Option<String>,
+    pub tool_use_id: Option<String>,
+    pub message_uuids: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionTree {
+    pub session_id: String,
+    /// All nodes in file order
+    pub nodes: Vec<TreeNode>,
+    pub roots: Vec<String>,
+    pub active_leaf: Option<String>,
+    /// uuids from the root to the active leaf
+    pub active_path: Vec<String>,
+    pub sidechains: Vec<SidechainThread>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FileChangeKind {
+    SessionUpdated,
+    TodoChanged,
+    SettingsChanged,
+    CommandChanged,
+    AgentChanged,
+    IdeLockAdded,
+    IdeLockRemoved,
+}
+
+impl FileChangeKind {
+    /// Name of the Tauri event emitted for this kind of change
+    pub fn event_name(&self) -> &'static str {
+        match self {
+            Self::SessionUpdated => "session-updated",
+            Self::TodoChanged => "todo-changed",
+            Self::SettingsChanged => "settings-changed",
+            Self::CommandChanged => "command-changed",
+            Self::AgentChanged => "agent-changed",
+            Self::IdeLockAdded => "ide-lock-added",
+            Self::IdeLockRemoved => "ide-lock-removed",
+        }
+    }
+}
+
+/// A coalesced batch of file changes of one kind
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FileChangeEvent {
+    pub kind: FileChangeKind,
+    /// Session ids, todo file stems, settings file names, command/agent names or IDE lock names
+    pub ids: Vec<String>,
+}
+
+/// Piece of a search snippet; matched terms are separate highlighted segments
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SnippetSegment {
+    pub text: String,
+    pub highlighted: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MessageSearchHit {
+    pub session_id: String,
+    pub project_path: String,
+    pub message_uuid: String,
+    /// "user" or "assistant"
+    pub message_type: String,
+    pub timestamp: DateTime<Utc>,
+    pub score: f64,
+    pub snippet: Vec<SnippetSegment>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommandFrequency {
+    /// Program and subcommand, e.g. "cargo test" or "npm run build"
+    pub command: String,
+    pub count: usize,
+    /// Runs with a tool_result that succeeded or failed
+    pub completed: usize,
+    pub failures: usize,
+    /// failures / completed, None when no run has completed
+    pub failure_rate: Option<f64>,
+    pub last_run: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommandCount {
+    pub key: String,
+    pub count: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommandAnalytics {
+    pub total_commands: usize,
+    pub top_commands: Vec<CommandFrequency>,
+    pub by_project: Vec<CommandCount>,
+    /// Local calendar days, oldest first
+    pub by_day: Vec<CommandCount>,
+    /// Longest-running commands with a known duration
+    pub slowest: Vec<CommandLogEntry>,
+}
@@ -0,0 +1,85 @@
+use crate::error::{ManagerError, ManagerResult};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+/// Directories the app may read and write files in
+///
+/// Only `~/.claude` and the `.claude` directories of projects Claude Code has
+/// been used in are allowed. Paths are resolved through symlinks before they
+/// are checked, so a link cannot lead out of an allowed directory.
+pub struct PathPolicy {
+    roots: Vec<PathBuf>,
+}
+
+impl PathPolicy {
+    pub fn new(claude_dir: &Path, project_paths: impl IntoIterator<Item = PathBuf>) -> Self {
+        let roots = std::iter::once(claude_dir.to_path_buf())
+            .chain(
+                project_paths
+                    .into_iter()
+                    .map(|project| project.join(".claude")),
+            )
+            .filter_map(|root| canonicalize_lenient(&root))
+            .collect();
+        Self { roots }
+    }
+
+    /// The real location of `path`, if it is a file inside an allowed directory
+    ///
+    /// `path` does not have to exist yet, but every part of it that does is
+    /// resolved through symlinks. A final component that is a dangling symlink
+    /// is rejected, since writing through it would land outside the check.
+    pub fn resolve(&self, path: &Path) -> ManagerResult<PathBuf> {
+        let denied = || {
+            ManagerError::permission_denied(format!(
+                "File must be within a .claude directory of ~/.claude or a known project: {}",
+                path.display()
+            ))
+            .with_path(path)
+        };
+        let resolved = canonicalize_lenient(path).ok_or_else(denied)?;
+        let inside = self
+            .roots
+            .iter()
+            .any(|root| resolved.starts_with(root) && resolved != *root);
+        if inside {
+            Ok(resolved)
+        } else {
+            Err(denied())
+        }
+    }
+}
+
+/// `fs::canonicalize` for paths whose last components may not exist yet
+///
+/// The existing part is resolved through symlinks and the rest appended as is.
+/// Relative paths and paths with `..` are refused rather than resolved.
+fn canonicalize_lenient(path: &Path) -> Option<PathBuf> {
+    if !path.is_absolute() || path.components().any(|c| c == Component::ParentDir) {
+        return None;
+    }
+
+    let mut existing = path;
+    let mut missing = Vec::new();
+    while fs::symlink_metadata(existing).is_err() {
+        missing.push(existing.file_name()?);
+        existing = existing.parent()?;
+    }
+    let mut resolved = fs::canonicalize(existing).ok()?;
+    resolved.extend(missing.into_iter().rev());
+    Some(resolved)
+}
+
+/// Check that `name` names a single file, so it cannot point into another directory
+pub fn check_file_name(name: &str) -> ManagerResult<()> {
+    let valid = !name.is_empty()
+        && name != "."
+        && name != ".."
+        && !name.contains(['/', '\\', '\0'])
+        && !(cfg!(windows) && name.contains(':'));
+    if valid {
+        Ok(())
+    } else {
+        Err(ManagerError::validation(format!("Invalid name: {name:?}")))
+    }
+}
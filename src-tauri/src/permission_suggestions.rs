@@ -0,0 +1,261 @@
+use crate::command_analytics::{command_segments, normalize_command};
+use crate::error::{ManagerError, ManagerResult};
+use crate::models::{PermissionSuggestion, ToolCallPreview};
+use crate::permissions::{
+    is_within, resolve_file_path, url_host, PermissionRule, ToolCall, PROMPTLESS_TOOLS,
+};
+use chrono::{DateTime, Utc};
+use std::cmp::Reverse;
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+pub const DEFAULT_SUGGESTION_LIMIT: usize = 20;
+
+/// A rule is only worth suggesting once it would have saved more than one prompt
+const MIN_SUGGESTION_USES: usize = 2;
+
+/// Historic calls shown with each suggestion
+const PREVIEW_LIMIT: usize = 10;
+
+/// A tool call from a session that the user let run
+#[derive(Debug, Clone)]
+pub struct ObservedToolCall {
+    pub session_id: String,
+    pub tool_name: String,
+    pub input: serde_json::Value,
+    pub cwd: Option<String>,
+    pub timestamp: Option<DateTime<Utc>>,
+    /// Permission mode the session was in, when the session file records it
+    pub permission_mode: Option<String>,
+}
+
+impl ObservedToolCall {
+    pub fn as_call(&self) -> ToolCall<'_> {
+        ToolCall {
+            tool_name: &self.tool_name,
+            input: &self.input,
+            cwd: self.cwd.as_deref(),
+        }
+    }
+}
+
+/// Rejected calls get this tool_result instead of running
+fn is_rejection(text: &str) -> bool {
+    text.contains("doesn't want to proceed with this tool use")
+        || text.contains("tool use was rejected")
+}
+
+fn tool_result_text(block: &serde_json::Value) -> String {
+    match block.get("content") {
+        Some(serde_json::Value::String(text)) => text.clone(),
+        Some(serde_json::Value::Array(items)) => items
+            .iter()
+            .filter_map(|item| item.get("text").and_then(|t| t.as_str()))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        _ => String::new(),
+    }
+}
+
+/// Tool calls in a session file that received a result other than a rejection
+///
+/// Whether a call actually prompted depends on the settings and permission
+/// mode it ran under, which callers check with a `PermissionPolicy`.
+pub fn approved_tool_calls<'a>(
+    lines: impl IntoIterator<Item = &'a str>,
+    session_id: &str,
+) -> Vec<ObservedToolCall> {
+    let mut pending: HashMap<String, ObservedToolCall> = HashMap::new();
+    let mut approved = Vec::new();
+    let mut permission_mode: Option<String> = None;
+
+    for line in lines {
+        let Ok(raw) = serde_json::from_str::<serde_json::Value>(line) else {
+            continue;
+        };
+        // User entries record the mode in effect, which can change mid-session
+        if let Some(mode) = raw.get("permissionMode").and_then(|m| m.as_str()) {
+            permission_mode = Some(mode.to_string());
+        }
+        let blocks = raw
+            .get("message")
+            .and_then(|m| m.get("content"))
+            .and_then(|c| c.as_array());
+
+        for block in blocks.into_iter().flatten() {
+            match block.get("type").and_then(|t| t.as_str()) {
+                Some("tool_use") => {
+                    let (Some(id), Some(name)) = (
+                        block.get("id").and_then(|i| i.as_str()),
+                        block.get("name").and_then(|n| n.as_str()),
+                    ) else {
+                        continue;
+                    };
+                    pending.insert(
+                        id.to_string(),
+                        ObservedToolCall {
+                            session_id: session_id.to_string(),
+                            tool_name: name.to_string(),
+                            input: block.get("input").cloned().unwrap_or_default(),
+                            cwd: raw.get("cwd").and_then(|c| c.as_str()).map(str::to_string),
+                            timestamp: raw
+                                .get("timestamp")
+                                .and_then(|t| t.as_str())
+                                .and_then(|t| t.parse().ok()),
+                            permission_mode: permission_mode.clone(),
+                        },
+                    );
+                }
+                Some("tool_result") => {
+                    let Some(call) = block
+                        .get("tool_use_id")
+                        .and_then(|id| id.as_str())
+                        .and_then(|id| pending.remove(id))
+                    else {
+                        continue;
+                    };
+                    if !is_rejection(&tool_result_text(block)) {
+                        approved.push(call);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    approved
+}
+
+/// The narrowest rule that would have allowed `call` without a prompt
+pub fn candidate_rule(call: &ToolCall) -> Option<String> {
+    let tool = call.tool_name;
+    if PROMPTLESS_TOOLS.contains(&tool) {
+        return None;
+    }
+
+    match tool {
+        "Bash" => {
+            let command = call.command()?.trim();
+            if command.is_empty() || command_segments(command).len() != 1 {
+                return None;
+            }
+            // Prefer a `program subcommand:*` prefix when the command starts with it.
+            // A bare program like `rm`, `python3 script.py` or `bash -c` stays exact,
+            // since `Bash(rm:*)` would allow anything that program can be told to do.
+            let prefix = normalize_command(command)?;
+            let has_prefix = prefix.contains(' ')
+                && command
+                    .strip_prefix(prefix.as_str())
+                    .is_some_and(|rest| rest.is_empty() || rest.starts_with(char::is_whitespace));
+            Some(if has_prefix {
+                format!("Bash({prefix}:*)")
+            } else {
+                format!("Bash({command})")
+            })
+        }
+        "Edit" | "MultiEdit" | "Write" | "NotebookEdit" | "Read" => {
+            let path = resolve_file_path(call.file_path()?, call.cwd)?;
+            let inside_cwd = call
+                .cwd
+                .and_then(|cwd| resolve_file_path(cwd, None))
+                .is_some_and(|cwd| is_within(&path, &cwd));
+            // Reading inside the working directory never prompts
+            if tool == "Read" && inside_cwd {
+                return None;
+            }
+
+            let rule_tool = if tool == "Read" { "Read" } else { "Edit" };
+            let (directory, _) = path.rsplit_once('/')?;
+            // Everything below a filesystem or drive root is far too broad; allow the file alone
+            let is_root =
+                directory.is_empty() || (directory.len() == 3 && directory.ends_with(':'));
+            Some(if is_root {
+                format!("{rule_tool}(/{path})")
+            } else {
+                format!("{rule_tool}(/{directory}/**)")
+            })
+        }
+        "WebFetch" => Some(format!("WebFetch(domain:{})", url_host(call.url()?)?)),
+        _ => Some(tool.to_string()),
+    }
+}
+
+/// Rules that would have allowed calls the user approved repeatedly, most used first
+///
+/// `calls` should only hold calls the user was prompted for. Each suggestion
+/// previews the most recent calls it matches.
+pub fn suggest_permission_rules(
+    calls: &[ObservedToolCall],
+    limit: usize,
+) -> Vec<PermissionSuggestion> {
+    let candidates: BTreeSet<String> = calls
+        .iter()
+        .filter_map(|call| candidate_rule(&call.as_call()))
+        .collect();
+
+    let mut suggestions: Vec<PermissionSuggestion> = candidates
+        .into_iter()
+        .filter_map(|rule| {
+            let parsed = PermissionRule::parse(&rule)?;
+            let mut matched: Vec<&ObservedToolCall> = calls
+                .iter()
+                .filter(|call| parsed.matches(&call.as_call()))
+                .collect();
+            if matched.len() < MIN_SUGGESTION_USES {
+                return None;
+            }
+
+            matched.sort_by_key(|call| Reverse(call.timestamp));
+            let sessions: HashSet<&str> = matched
+                .iter()
+                .map(|call| call.session_id.as_str())
+                .collect();
+            Some(PermissionSuggestion {
+                tool_name: parsed.tool.clone(),
+                rule,
+                count: matched.len(),
+                session_count: sessions.len(),
+                last_used: matched.first().and_then(|call| call.timestamp),
+                matches: matched
+                    .iter()
+                    .take(PREVIEW_LIMIT)
+                    .map(|call| ToolCallPreview {
+                        session_id: call.session_id.clone(),
+                        tool_name: call.tool_name.clone(),
+                        summary: call.as_call().summary(),
+                        cwd: call.cwd.clone(),
+                        timestamp: call.timestamp,
+                    })
+                    .collect(),
+            })
+        })
+        .collect();
+
+    suggestions.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.rule.cmp(&b.rule)));
+    suggestions.truncate(limit);
+    suggestions
+}
+
+/// Add `rule` to `permissions.allow` of a settings document, keeping every other key
+///
+/// Returns false when the rule was already allowed.
+pub fn merge_allow_rule(settings: &mut serde_json::Value, rule: &str) -> ManagerResult<bool> {
+    let root = settings
+        .as_object_mut()
+        .ok_or_else(|| ManagerError::parse("Settings file is not a JSON object"))?;
+    let permissions = root
+        .entry("permissions")
+        .or_insert_with(|| serde_json::json!({}))
+        .as_object_mut()
+        .ok_or_else(|| ManagerError::parse("`permissions` is not a JSON object"))?;
+    let allow = permissions
+        .entry("allow")
+        .or_insert_with(|| serde_json::json!([]))
+        .as_array_mut()
+        .ok_or_else(|| ManagerError::parse("`permissions.allow` is not an array"))?;
+
+    if allow.iter().any(|existing| existing.as_str() == Some(rule)) {
+        return Ok(false);
+    }
+    allow.push(serde_json::Value::String(rule.to_string()));
+    Ok(true)
+}
@@ -0,0 +1,531 @@
+use crate::command_analytics::command_segments;
+use crate::models::{
+    PermissionDecision, PermissionSettings, PermissionSimulation, SourcedPermissionRule,
+};
+use std::fmt;
+
+/// Tools governed by `Edit(...)` rules
+const EDIT_TOOLS: &[&str] = &["Edit", "MultiEdit", "Write", "NotebookEdit"];
+
+/// Tools governed by `Read(...)` rules
+const READ_TOOLS: &[&str] = &["Read", "NotebookRead"];
+
+/// Tools that never ask for permission
+pub const PROMPTLESS_TOOLS: &[&str] = &[
+    "Task",
+    "TodoWrite",
+    "Glob",
+    "Grep",
+    "LS",
+    "NotebookRead",
+    "ExitPlanMode",
+    "BashOutput",
+    "KillShell",
+];
+
+/// A single tool invocation, as found in a session's tool_use blocks
+#[derive(Debug, Clone, Copy)]
+pub struct ToolCall<'a> {
+    pub tool_name: &'a str,
+    pub input: &'a serde_json::Value,
+    /// Working directory of the session, used to resolve relative path rules
+    pub cwd: Option<&'a str>,
+}
+
+impl ToolCall<'_> {
+    pub fn command(&self) -> Option<&str> {
+        self.input.get("command").and_then(|c| c.as_str())
+    }
+
+    /// Path of the file a file tool works on
+    pub fn file_path(&self) -> Option<&str> {
+        ["file_path", "notebook_path", "path"]
+            .iter()
+            .find_map(|key| self.input.get(*key).and_then(|p| p.as_str()))
+    }
+
+    pub fn url(&self) -> Option<&str> {
+        self.input.get("url").and_then(|u| u.as_str())
+    }
+
+    /// What the call did in one line: the command, file or URL
+    pub fn summary(&self) -> String {
+        self.command()
+            .or_else(|| self.file_path())
+            .or_else(|| self.url())
+            .or_else(|| self.input.get("query").and_then(|q| q.as_str()))
+            .unwrap_or_default()
+            .to_string()
+    }
+}
+
+/// A permission rule such as `Bash`, `Bash(npm test:*)`, `Edit(src/**)` or
+/// `WebFetch(domain:example.com)`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PermissionRule {
+    pub tool: String,
+    pub specifier: Option<String>,
+}
+
+impl PermissionRule {
+    pub fn parse(rule: &str) -> Option<Self> {
+        let rule = rule.trim();
+        let (tool, specifier) = match rule.find('(') {
+            Some(open) => {
+                let specifier = rule[open + 1..].strip_suffix(')')?;
+                (&rule[..open], Some(specifier.to_string()))
+            }
+            None => (rule, None),
+        };
+        // MCP tool names may contain dashes, e.g. `mcp__my-server__search`
+        let valid_char = |c: char| c.is_ascii_alphanumeric() || c == '_' || c == '-';
+        if tool.is_empty() || !tool.chars().all(valid_char) {
+            return None;
+        }
+
+        Some(Self {
+            tool: tool.to_string(),
+            // `Bash()` and `Bash(*)` cover every call, like `Bash`
+            specifier: specifier.filter(|s| !s.is_empty() && s != "*"),
+        })
+    }
+
+    fn covers_tool(&self, tool_name: &str) -> bool {
+        if self.tool == tool_name {
+            return true;
+        }
+        // `mcp__server` covers every tool of that server
+        if self.tool.starts_with("mcp__")
+            && tool_name
+                .strip_prefix(self.tool.as_str())
+                .is_some_and(|rest| rest.starts_with("__"))
+        {
+            return self.specifier.is_none();
+        }
+        match self.tool.as_str() {
+            "Edit" => EDIT_TOOLS.contains(&tool_name),
+            "Read" => READ_TOOLS.contains(&tool_name),
+            _ => false,
+        }
+    }
+
+    pub fn matches(&self, call: &ToolCall) -> bool {
+        if !self.covers_tool(call.tool_name) {
+            return false;
+        }
+        let Some(specifier) = self.specifier.as_deref() else {
+            return true;
+        };
+
+        match self.tool.as_str() {
+            "Bash" => call
+                .command()
+                .is_some_and(|command| bash_matches(specifier, command)),
+            "Edit" | "Read" => call
+                .file_path()
+                .is_some_and(|path| path_matches(specifier, path, call.cwd)),
+            "WebFetch" => match (specifier.strip_prefix("domain:"), call.url()) {
+                (Some(domain), Some(url)) => {
+                    url_host(url).is_some_and(|host| host.eq_ignore_ascii_case(domain))
+                }
+                _ => false,
+            },
+            // Specifiers of other tools are not understood, so they never match
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for PermissionRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.specifier {
+            Some(specifier) => write!(f, "{}({})", self.tool, specifier),
+            None => write!(f, "{}", self.tool),
+        }
+    }
+}
+
+/// `npm test:*` matches `npm test` and `npm test <args>`, but not `npm testing`;
+/// anything else is exact
+fn bash_matches(specifier: &str, command: &str) -> bool {
+    let command = command.trim();
+    match specifier.strip_suffix(":*") {
+        Some(prefix) => command
+            .strip_prefix(prefix.trim())
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with(char::is_whitespace)),
+        None => command == specifier.trim(),
+    }
+}
+
+/// Every command a Bash command line runs: its `&&`, `;` and `|` parts plus,
+/// recursively, the bodies of command and process substitutions
+///
+/// The flag tells whether the line has substitutions at all.
+fn bash_commands(command: &str) -> (Vec<String>, bool) {
+    let mut commands = command_segments(command);
+    let bodies = substitution_bodies(command);
+    let substituted = !bodies.is_empty();
+    for body in bodies {
+        commands.extend(bash_commands(&body).0);
+    }
+    (commands, substituted)
+}
+
+/// Bodies of `$(...)`, backtick, `<(...)` and `>(...)` substitutions outside single quotes
+fn substitution_bodies(command: &str) -> Vec<String> {
+    let chars: Vec<char> = command.chars().collect();
+    let mut bodies = Vec::new();
+    let mut in_single = false;
+    let mut in_double = false;
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+        if in_single {
+            in_single = c != '\'';
+            i += 1;
+            continue;
+        }
+
+        match c {
+            '\\' => {
+                i += 2;
+                continue;
+            }
+            '\'' if !in_double => in_single = true,
+            '"' => in_double = !in_double,
+            '`' => {
+                let end = (i + 1..chars.len())
+                    .find(|&j| chars[j] == '`' && chars[j - 1] != '\\')
+                    .unwrap_or(chars.len());
+                bodies.push(chars[i + 1..end].iter().collect());
+                i = end + 1;
+                continue;
+            }
+            '$' | '<' | '>' if next == Some('(') && (c == '$' || !in_double) => {
+                // An unbalanced substitution runs to the end of the line
+                let mut depth = 0;
+                let end = (i + 1..chars.len())
+                    .find(|&j| {
+                        match chars[j] {
+                            '(' => depth += 1,
+                            ')' => depth -= 1,
+                            _ => {}
+                        }
+                        depth == 0
+                    })
+                    .unwrap_or(chars.len());
+                bodies.push(chars[i + 2..end].iter().collect());
+                i = end + 1;
+                continue;
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+
+    bodies
+}
+
+/// Host of an `http(s)://` URL, without credentials or port
+pub fn url_host(url: &str) -> Option<&str> {
+    let rest = url.split_once("://")?.1;
+    let authority = rest.split(['/', '?', '#']).next()?;
+    let host = authority.rsplit('@').next()?;
+    let host = match host.strip_prefix('[') {
+        Some(ipv6) => ipv6.split(']').next()?,
+        None => host.split(':').next()?,
+    };
+    (!host.is_empty()).then_some(host)
+}
+
+/// Absolute form of a tool's file path with `.` and `..` resolved
+///
+/// Rules use forward slashes on every platform, so paths are compared as text:
+/// backslashes become `/` and a Windows drive path becomes `/C:/...`.
+pub fn resolve_file_path(file_path: &str, cwd: Option<&str>) -> Option<String> {
+    let file_path = file_path.replace('\\', "/");
+    let has_drive = file_path.as_bytes().get(1) == Some(&b':');
+    let joined = if file_path.starts_with('/') || has_drive {
+        file_path
+    } else {
+        format!("{}/{file_path}", cwd?.replace('\\', "/"))
+    };
+
+    let mut parts: Vec<&str> = Vec::new();
+    for part in joined.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                parts.pop();
+            }
+            part => parts.push(part),
+        }
+    }
+    Some(format!("/{}", parts.join("/")))
+}
+
+/// Whether resolved `path` is `directory` or below it
+pub fn is_within(path: &str, directory: &str) -> bool {
+    let directory = directory.trim_end_matches('/');
+    path == directory
+        || path
+            .strip_prefix(directory)
+            .is_some_and(|rest| rest.starts_with('/'))
+}
+
+/// Absolute glob for a path specifier, following gitignore conventions
+///
+/// `//abs/path` is absolute, `~/path` is under the home directory, and other
+/// patterns are relative to the session's working directory. A pattern without
+/// a slash matches at any depth.
+fn resolve_path_pattern(pattern: &str, cwd: Option<&str>) -> Option<String> {
+    if let Some(absolute) = pattern.strip_prefix("//") {
+        return Some(format!("/{absolute}"));
+    }
+    if let Some(home_relative) = pattern.strip_prefix("~/") {
+        let home = resolve_file_path(&dirs::home_dir()?.to_string_lossy(), None)?;
+        return Some(format!("{}/{home_relative}", home.trim_end_matches('/')));
+    }
+
+    let cwd = resolve_file_path(cwd?, None)?;
+    let cwd = cwd.trim_end_matches('/');
+    match pattern.strip_prefix('/') {
+        Some(rooted) => Some(format!("{cwd}/{rooted}")),
+        None if !pattern.trim_end_matches('/').contains('/') => Some(format!("{cwd}/**/{pattern}")),
+        None => Some(format!("{cwd}/{}", pattern.trim_start_matches("./"))),
+    }
+}
+
+fn path_matches(specifier: &str, file_path: &str, cwd: Option<&str>) -> bool {
+    let Some(pattern) = resolve_path_pattern(specifier, cwd) else {
+        return false;
+    };
+    let Some(file_path) = resolve_file_path(file_path, cwd) else {
+        return false;
+    };
+
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = file_path.chars().collect();
+    // `dir/` and `dir/**` both cover everything below dir
+    let pattern = match pattern.last() {
+        Some('/') => [pattern.as_slice(), &['*', '*']].concat(),
+        _ => pattern,
+    };
+    glob_match(&pattern, &text)
+}
+
+/// `*` and `?` stay within one path segment, `**` crosses segments and
+/// `/**/` also matches a single `/`
+fn glob_match(pattern: &[char], text: &[char]) -> bool {
+    match pattern {
+        [] => text.is_empty(),
+        ['/', '*', '*', '/', rest @ ..] => {
+            text.first() == Some(&'/')
+                && (0..text.len()).any(|i| text[i] == '/' && glob_match(rest, &text[i + 1..]))
+        }
+        ['*', '*', rest @ ..] => (0..=text.len()).any(|i| glob_match(rest, &text[i..])),
+        ['*', rest @ ..] => (0..=text.len())
+            .take_while(|i| *i == 0 || text[i - 1] != '/')
+            .any(|i| glob_match(rest, &text[i..])),
+        ['?', rest @ ..] => text.first().is_some_and(|c| *c != '/') && glob_match(rest, &text[1..]),
+        [c, rest @ ..] => text.first() == Some(c) && glob_match(rest, &text[1..]),
+    }
+}
+
+/// Allow, deny and ask rules from every settings layer, evaluated like Claude Code does
+///
+/// Deny rules win over ask rules, which win over allow rules, whichever file
+/// they come from. Calls no rule decides fall back to the permission mode.
+#[derive(Debug, Default)]
+pub struct PermissionPolicy {
+    default_mode: Option<String>,
+    rules: Vec<(PermissionRule, SourcedPermissionRule)>,
+    invalid_rules: Vec<SourcedPermissionRule>,
+}
+
+impl PermissionPolicy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a settings file's permissions; add layers from the highest precedence down
+    ///
+    /// `sets_mode` tells whether the file spells out `defaultMode` rather than
+    /// relying on its default. The first layer that does decides the mode.
+    pub fn add_layer(&mut self, source: &str, permissions: &PermissionSettings, sets_mode: bool) {
+        if sets_mode && self.default_mode.is_none() {
+            self.default_mode = Some(permissions.default_mode.clone());
+        }
+
+        for (list, rules) in [
+            (PermissionDecision::Deny, &permissions.deny),
+            (PermissionDecision::Ask, &permissions.ask),
+            (PermissionDecision::Allow, &permissions.allow),
+        ] {
+            for rule in rules {
+                let sourced = SourcedPermissionRule {
+                    rule: rule.clone(),
+                    list,
+                    source: source.to_string(),
+                };
+                match PermissionRule::parse(rule) {
+                    Some(parsed) => self.rules.push((parsed, sourced)),
+                    None => self.invalid_rules.push(sourced),
+                }
+            }
+        }
+    }
+
+    pub fn default_mode(&self) -> &str {
+        self.default_mode.as_deref().unwrap_or("default")
+    }
+
+    /// Decide a tool call: deny, ask or allow, and why
+    pub fn evaluate(&self, call: &ToolCall) -> PermissionSimulation {
+        self.evaluate_in_mode(call, self.default_mode())
+    }
+
+    /// Decide a tool call as if the session ran in permission `mode`
+    pub fn evaluate_in_mode(&self, call: &ToolCall, mode: &str) -> PermissionSimulation {
+        // Claude Code checks each part of a compound Bash command on its own
+        let (segments, substituted) = match (call.tool_name, call.command()) {
+            ("Bash", Some(command)) => bash_commands(command),
+            _ => (Vec::new(), false),
+        };
+        let segment_inputs: Vec<serde_json::Value> = segments
+            .iter()
+            .map(|segment| serde_json::json!({ "command": segment }))
+            .collect();
+        let segment_calls: Vec<ToolCall> = segment_inputs
+            .iter()
+            .map(|input| ToolCall { input, ..*call })
+            .collect();
+
+        let matched_rules: Vec<&SourcedPermissionRule> = self
+            .rules
+            .iter()
+            .filter(|(rule, _)| {
+                rule.matches(call) || segment_calls.iter().any(|part| rule.matches(part))
+            })
+            .map(|(_, sourced)| sourced)
+            .collect();
+        let first_in = |list: PermissionDecision| {
+            matched_rules
+                .iter()
+                .find(|sourced| sourced.list == list)
+                .map(|sourced| (*sourced).clone())
+        };
+
+        let (decision, decided_by, reason) = if let Some(rule) = first_in(PermissionDecision::Deny)
+        {
+            (
+                PermissionDecision::Deny,
+                Some(rule),
+                "Denied by rule".to_string(),
+            )
+        } else if let Some(rule) = first_in(PermissionDecision::Ask) {
+            (
+                PermissionDecision::Ask,
+                Some(rule),
+                "Ask rules prompt even when an allow rule matches".to_string(),
+            )
+        } else if let Some(rule) = self.allowing_rule(call, &segment_calls, substituted) {
+            (
+                PermissionDecision::Allow,
+                Some(rule),
+                "Allowed by rule".to_string(),
+            )
+        } else {
+            let (decision, reason) = Self::mode_decision(call, mode);
+            let reason = match decision {
+                PermissionDecision::Ask if substituted => {
+                    "Commands inside $(...) or backticks are never allowed by a rule, so the user is asked".to_string()
+                }
+                _ => reason,
+            };
+            (decision, None, reason)
+        };
+
+        PermissionSimulation {
+            decision,
+            decided_by,
+            reason,
+            matched_rules: matched_rules.into_iter().cloned().collect(),
+            invalid_rules: self.invalid_rules.clone(),
+        }
+    }
+
+    /// The allow rule covering the call; compound commands need every part allowed
+    ///
+    /// A substitution can hide any command behind an allowed prefix, as in
+    /// `echo $(rm -rf ~)`, so `substituted` calls are never allowed by a rule.
+    fn allowing_rule(
+        &self,
+        call: &ToolCall,
+        segment_calls: &[ToolCall],
+        substituted: bool,
+    ) -> Option<SourcedPermissionRule> {
+        if substituted {
+            return None;
+        }
+        let allowing = |call: &ToolCall| {
+            self.rules
+                .iter()
+                .find(|(rule, sourced)| {
+                    sourced.list == PermissionDecision::Allow && rule.matches(call)
+                })
+                .map(|(_, sourced)| sourced.clone())
+        };
+
+        // `Bash(cargo build:*)` must not allow `cargo build && curl ... | sh`
+        if segment_calls.len() < 2 {
+            return allowing(call);
+        }
+        let mut rules = segment_calls.iter().map(allowing);
+        let first = rules.next()??;
+        rules.all(|rule| rule.is_some()).then_some(first)
+    }
+
+    fn mode_decision(call: &ToolCall, mode: &str) -> (PermissionDecision, String) {
+        let tool = call.tool_name;
+        if mode == "bypassPermissions" {
+            return (
+                PermissionDecision::Allow,
+                "bypassPermissions mode allows every call no rule denies".to_string(),
+            );
+        }
+        if mode == "acceptEdits" && EDIT_TOOLS.contains(&tool) {
+            return (
+                PermissionDecision::Allow,
+                "acceptEdits mode allows file edits".to_string(),
+            );
+        }
+        if PROMPTLESS_TOOLS.contains(&tool) {
+            return (
+                PermissionDecision::Allow,
+                format!("{tool} does not need permission"),
+            );
+        }
+        if tool == "Read" {
+            let inside_cwd = match (call.file_path(), call.cwd) {
+                (Some(path), Some(cwd)) => resolve_file_path(path, Some(cwd))
+                    .zip(resolve_file_path(cwd, None))
+                    .is_some_and(|(path, cwd)| is_within(&path, &cwd)),
+                _ => false,
+            };
+            if inside_cwd {
+                return (
+                    PermissionDecision::Allow,
+                    "Reading inside the working directory does not need permission".to_string(),
+                );
+            }
+        }
+
+        (
+            PermissionDecision::Ask,
+            format!("No rule matches, so {mode} mode asks the user"),
+        )
+    }
+}
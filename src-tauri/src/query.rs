@@ -0,0 +1,447 @@
+use crate::models::{ClaudeSession, CommandLogEntry};
+use chrono::{DateTime, Local, NaiveDate, TimeZone, Utc};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+/// What a query is evaluated against; decides which fields are accepted
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QueryScope {
+    Sessions,
+    Commands,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionStatus {
+    Processing,
+    Idle,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Filter {
+    /// Free text, bare or quoted
+    Text(String),
+    Project(String),
+    Branch(String),
+    Session(String),
+    Tool(String),
+    Model(String),
+    Status(SessionStatus),
+    /// Inclusive lower bound
+    After(DateTime<Utc>),
+    /// Exclusive upper bound
+    Before(DateTime<Utc>),
+}
+
+impl Filter {
+    /// Tool and model filters need the session's messages, not just its summary
+    pub fn needs_messages(&self) -> bool {
+        matches!(self, Filter::Tool(_) | Filter::Model(_))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Clause {
+    pub filter: Filter,
+    /// Written with a leading `-`
+    pub negated: bool,
+    /// Character offset of the clause in the query
+    pub position: usize,
+}
+
+/// A parsed query: every clause must hold
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Query {
+    pub clauses: Vec<Clause>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueryError {
+    /// Character offset of the offending input
+    pub position: usize,
+    pub message: String,
+}
+
+impl QueryError {
+    fn new(position: usize, message: impl Into<String>) -> Self {
+        Self {
+            position,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at position {}", self.message, self.position)
+    }
+}
+
+impl std::error::Error for QueryError {}
+
+/// Something a query can be evaluated against
+pub trait QueryTarget {
+    /// Whether the filter holds, or None if the target cannot tell yet
+    fn test(&self, filter: &Filter) -> Option<bool>;
+}
+
+/// Field names accepted before a colon
+const FIELDS: &[&str] = &[
+    "project", "branch", "session", "tool", "model", "status", "after", "before",
+];
+
+struct Parser {
+    chars: Vec<char>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).copied()
+    }
+
+    fn at_boundary(&self) -> bool {
+        self.peek().is_none_or(char::is_whitespace)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.position += 1;
+        }
+    }
+
+    /// Read a `"..."` string starting at the opening quote; `\"` and `\\` are escapes
+    fn quoted(&mut self) -> Result<String, QueryError> {
+        let open = self.position;
+        self.position += 1;
+        let mut value = String::new();
+        loop {
+            match self.peek() {
+                None => return Err(QueryError::new(open, "Unterminated quote")),
+                Some('"') => {
+                    self.position += 1;
+                    break;
+                }
+                Some('\\') if matches!(self.chars.get(self.position + 1), Some('"' | '\\')) => {
+                    value.push(self.chars[self.position + 1]);
+                    self.position += 2;
+                }
+                Some(c) => {
+                    value.push(c);
+                    self.position += 1;
+                }
+            }
+        }
+
+        if !self.at_boundary() {
+            return Err(QueryError::new(
+                self.position,
+                "Expected a space after the closing quote",
+            ));
+        }
+        Ok(value)
+    }
+
+    fn word(&mut self) -> String {
+        let mut value = String::new();
+        while let Some(c) = self.peek().filter(|c| !c.is_whitespace()) {
+            value.push(c);
+            self.position += 1;
+        }
+        value
+    }
+
+    /// End of a known `field:` prefix starting at `start`, i.e. the index of its colon
+    fn known_field_at(&self, start: usize) -> Option<usize> {
+        let mut end = start;
+        while self.chars.get(end).is_some_and(|c| c.is_ascii_alphabetic()) {
+            end += 1;
+        }
+        if end == start || self.chars.get(end) != Some(&':') {
+            return None;
+        }
+
+        let name: String = self.chars[start..end].iter().collect();
+        FIELDS
+            .contains(&name.to_lowercase().as_str())
+            .then_some(end)
+    }
+
+    /// Known `field:` prefix at the current position, consumed if present
+    ///
+    /// Other `word:` prefixes, as in `npm run test:unit` or a URL, are plain text.
+    fn field_name(&mut self) -> Option<String> {
+        let end = self.known_field_at(self.position)?;
+        let name: String = self.chars[self.position..end].iter().collect();
+        self.position = end + 1;
+        Some(name)
+    }
+
+    /// A leading `-` negates a known field or a quoted phrase; elsewhere it is
+    /// part of the text, as in `git commit -m`
+    fn negation_at(&self, position: usize) -> bool {
+        self.chars.get(position) == Some(&'-')
+            && (self.chars.get(position + 1) == Some(&'"')
+                || self.known_field_at(position + 1).is_some())
+    }
+}
+
+fn parse_date(value: &str, position: usize) -> Result<DateTime<Utc>, QueryError> {
+    if let Ok(timestamp) = DateTime::parse_from_rfc3339(value) {
+        return Ok(timestamp.with_timezone(&Utc));
+    }
+
+    let date = NaiveDate::parse_from_str(value, "%Y-%m-%d").map_err(|_| {
+        QueryError::new(
+            position,
+            format!("Invalid date `{value}`, expected YYYY-MM-DD or an RFC 3339 timestamp"),
+        )
+    })?;
+    // Dates are local calendar days, like the rest of the UI
+    let midnight = date.and_hms_opt(0, 0, 0).unwrap_or_default();
+    Local
+        .from_local_datetime(&midnight)
+        .earliest()
+        .map(|local| local.with_timezone(&Utc))
+        .ok_or_else(|| QueryError::new(position, format!("Date `{value}` does not exist locally")))
+}
+
+fn build_filter(
+    field: &str,
+    value: String,
+    field_position: usize,
+    value_position: usize,
+    scope: QueryScope,
+) -> Result<Filter, QueryError> {
+    let filter = match field.to_lowercase().as_str() {
+        "project" => Filter::Project(value),
+        "branch" => Filter::Branch(value),
+        "session" => Filter::Session(value),
+        "tool" => Filter::Tool(value),
+        "model" => Filter::Model(value),
+        "status" => match value.to_lowercase().as_str() {
+            "processing" | "active" => Filter::Status(SessionStatus::Processing),
+            "idle" | "completed" => Filter::Status(SessionStatus::Idle),
+            _ => {
+                return Err(QueryError::new(
+                    value_position,
+                    format!("Unknown status `{value}`, expected processing or idle"),
+                ))
+            }
+        },
+        "after" => Filter::After(parse_date(&value, value_position)?),
+        // `before:2026-09-01` includes nothing from that day
+        "before" => Filter::Before(parse_date(&value, value_position)?),
+        _ => {
+            return Err(QueryError::new(
+                field_position,
+                format!("Unknown field `{field}`"),
+            ))
+        }
+    };
+
+    let supported = match scope {
+        QueryScope::Sessions => true,
+        QueryScope::Commands => matches!(
+            filter,
+            Filter::Text(_)
+                | Filter::Project(_)
+                | Filter::Branch(_)
+                | Filter::Session(_)
+                | Filter::After(_)
+                | Filter::Before(_)
+        ),
+    };
+    if !supported {
+        return Err(QueryError::new(
+            field_position,
+            format!("Field `{field}` is not supported when searching commands"),
+        ));
+    }
+
+    Ok(filter)
+}
+
+impl Query {
+    /// Parse `field:value`, `-field:value`, bare words, `"quoted text"` and `-"quoted text"`
+    ///
+    /// Fields: project, branch, session, tool, model, status, after, before.
+    /// Commands only support project, branch, session, after and before.
+    /// Anything else, including unknown `word:` prefixes and bare `-flags`,
+    /// is searched for as text.
+    pub fn parse(input: &str, scope: QueryScope) -> Result<Query, QueryError> {
+        let mut parser = Parser {
+            chars: input.chars().collect(),
+            position: 0,
+        };
+        let mut clauses = Vec::new();
+
+        loop {
+            parser.skip_whitespace();
+            if parser.peek().is_none() {
+                break;
+            }
+
+            let position = parser.position;
+            let negated = parser.negation_at(position);
+            if negated {
+                parser.position += 1;
+            }
+
+            let field_position = parser.position;
+            let filter = if parser.peek() == Some('"') {
+                Filter::Text(parser.quoted()?)
+            } else if let Some(field) = parser.field_name() {
+                let value_position = parser.position;
+                let value = if parser.peek() == Some('"') {
+                    parser.quoted()?
+                } else {
+                    parser.word()
+                };
+                if value.is_empty() {
+                    return Err(QueryError::new(
+                        value_position,
+                        format!("Missing value for `{field}:`"),
+                    ));
+                }
+                build_filter(&field, value, field_position, value_position, scope)?
+            } else {
+                Filter::Text(parser.word())
+            };
+
+            clauses.push(Clause {
+                filter,
+                negated,
+                position,
+            });
+        }
+
+        Ok(Query { clauses })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.clauses.is_empty()
+    }
+
+    pub fn needs_messages(&self) -> bool {
+        self.clauses.iter().any(|c| c.filter.needs_messages())
+    }
+
+    pub fn text_terms(&self) -> impl Iterator<Item = &str> {
+        self.clauses.iter().filter_map(|c| match &c.filter {
+            Filter::Text(text) => Some(text.as_str()),
+            _ => None,
+        })
+    }
+
+    /// False once any clause is known to fail; undecided clauses are skipped
+    pub fn may_match(&self, target: &impl QueryTarget) -> bool {
+        self.clauses.iter().all(|clause| {
+            target
+                .test(&clause.filter)
+                .is_none_or(|matched| matched != clause.negated)
+        })
+    }
+
+    /// True only if every clause is known to hold
+    pub fn matches(&self, target: &impl QueryTarget) -> bool {
+        self.clauses.iter().all(|clause| {
+            target
+                .test(&clause.filter)
+                .is_some_and(|matched| matched != clause.negated)
+        })
+    }
+}
+
+fn contains_ignore_case(haystack: &str, needle: &str) -> bool {
+    haystack.to_lowercase().contains(&needle.to_lowercase())
+}
+
+/// Case-insensitive equality; a trailing `*` matches any suffix
+fn equals_pattern(value: &str, pattern: &str) -> bool {
+    let value = value.to_lowercase();
+    let pattern = pattern.to_lowercase();
+    match pattern.strip_suffix('*') {
+        Some(prefix) => value.starts_with(prefix),
+        None => value == pattern,
+    }
+}
+
+/// Tool names and models seen in a session's messages
+#[derive(Debug, Clone, Default)]
+pub struct MessageFacts {
+    pub tools: HashSet<String>,
+    pub models: HashSet<String>,
+}
+
+pub struct SessionTarget<'a> {
+    pub session: &'a ClaudeSession,
+    /// Loaded only when the query filters on tools or models
+    pub facts: Option<&'a MessageFacts>,
+    /// Free-text term -> sessions whose message content contains it
+    pub content_matches: &'a HashMap<String, HashSet<String>>,
+}
+
+impl QueryTarget for SessionTarget<'_> {
+    fn test(&self, filter: &Filter) -> Option<bool> {
+        let session = self.session;
+        let matched = match filter {
+            Filter::Text(text) => {
+                contains_ignore_case(&session.project_path, text)
+                    || contains_ignore_case(&session.session_id, text)
+                    || session
+                        .git_branch
+                        .as_deref()
+                        .is_some_and(|b| contains_ignore_case(b, text))
+                    || session
+                        .latest_content_preview
+                        .as_deref()
+                        .is_some_and(|p| contains_ignore_case(p, text))
+                    || self
+                        .content_matches
+                        .get(text)
+                        .is_some_and(|ids| ids.contains(&session.session_id))
+            }
+            Filter::Project(project) => contains_ignore_case(&session.project_path, project),
+            Filter::Branch(branch) => session
+                .git_branch
+                .as_deref()
+                .is_some_and(|b| equals_pattern(b, branch)),
+            Filter::Session(id) => equals_pattern(&session.session_id, id),
+            Filter::Tool(tool) => self.facts?.tools.iter().any(|t| equals_pattern(t, tool)),
+            Filter::Model(model) => self
+                .facts?
+                .models
+                .iter()
+                .any(|m| contains_ignore_case(m, model)),
+            Filter::Status(SessionStatus::Processing) => session.is_processing,
+            Filter::Status(SessionStatus::Idle) => !session.is_processing,
+            Filter::After(after) => session.timestamp >= *after,
+            Filter::Before(before) => session.timestamp < *before,
+        };
+        Some(matched)
+    }
+}
+
+impl QueryTarget for CommandLogEntry {
+    fn test(&self, filter: &Filter) -> Option<bool> {
+        let matched = match filter {
+            Filter::Text(text) => contains_ignore_case(&self.command, text),
+            Filter::Project(project) => self
+                .cwd
+                .as_deref()
+                .is_some_and(|cwd| contains_ignore_case(cwd, project)),
+            Filter::Branch(branch) => self
+                .git_branch
+                .as_deref()
+                .is_some_and(|b| equals_pattern(b, branch)),
+            Filter::Session(id) => self
+                .session_id
+                .as_deref()
+                .is_some_and(|s| equals_pattern(s, id)),
+            Filter::After(after) => self.timestamp.is_some_and(|t| t >= *after),
+            Filter::Before(before) => self.timestamp.is_some_and(|t| t < *before),
+            _ => return None,
+        };
+        Some(matched)
+    }
+}
@@ -0,0 +1,315 @@
+use crate::error::{ManagerError, ManagerResult};
+use crate::models::{
+    ClaudeMessage, ContentBlock, ImageSource, MessageContent, Redaction, RedactionKind,
+    RedactionOptions, RedactionReport,
+};
+use regex::Regex;
+use serde_json::Value;
+use std::path::PathBuf;
+
+/// Secret patterns as (rule, regex). When a regex has a capture group only
+/// the group is redacted, so `password=hunter22` keeps its key.
+///
+/// More specific rules come first; their placeholders contain nothing the
+/// later, broader rules match.
+const SECRET_RULES: &[(&str, &str)] = &[
+    (
+        "private_key",
+        r"-----BEGIN [A-Z0-9 ]*PRIVATE KEY-----[\s\S]*?-----END [A-Z0-9 ]*PRIVATE KEY-----",
+    ),
+    (
+        "aws_access_key",
+        r"\b(?:AKIA|ASIA|AGPA|AIDA|AROA|ANPA|ANVA|AIPA)[0-9A-Z]{16}\b",
+    ),
+    (
+        "aws_secret_key",
+        r#"(?i)aws_?secret_?access_?key["']?\s*[=:]\s*["']?([A-Za-z0-9/+=]{40})"#,
+    ),
+    ("anthropic_api_key", r"\bsk-ant-[A-Za-z0-9_-]{20,}"),
+    ("openai_api_key", r"\bsk-(?:proj-)?[A-Za-z0-9_-]{20,}"),
+    (
+        "github_token",
+        r"\b(?:gh[pousr]_[A-Za-z0-9]{36,}|github_pat_[A-Za-z0-9_]{22,})",
+    ),
+    ("slack_token", r"\bxox[abposr]-[A-Za-z0-9-]{10,}"),
+    ("stripe_key", r"\b[sr]k_(?:live|test)_[A-Za-z0-9]{16,}"),
+    ("google_api_key", r"\bAIza[0-9A-Za-z_-]{35}"),
+    (
+        "jwt",
+        r"\beyJ[A-Za-z0-9_-]{10,}\.eyJ[A-Za-z0-9_-]{10,}\.[A-Za-z0-9_-]{10,}",
+    ),
+    ("bearer_token", r"(?i)\bbearer\s+([A-Za-z0-9._~+/-]{20,}=*)"),
+    (
+        "url_password",
+        r"\b[a-z][a-z0-9+.-]*://[^\s:/@]+:([^\s@/]+)@",
+    ),
+    (
+        "secret_assignment",
+        r#"(?i)\b[A-Z0-9_]*(?:password|passwd|secret|token|api_?key)[A-Z0-9_]*["']?\s*[=:]\s*["']?([^\s"'\[][^\s"',;]{7,})"#,
+    ),
+];
+
+const EMAIL_PATTERN: &str = r"\b[A-Za-z0-9._%+-]+@[A-Za-z0-9.-]+\.[A-Za-z]{2,}\b";
+
+/// Hosts on domains that only resolve inside a private network
+const INTERNAL_HOST_PATTERN: &str = r"(?i)\b(?:[a-z0-9](?:[a-z0-9-]*[a-z0-9])?\.)+(?:local|localdomain|internal|intranet|corp|lan|home\.arpa)\b";
+
+/// Machine details that identify the developer
+#[derive(Debug, Clone, Default)]
+pub struct RedactionContext {
+    pub home_dir: Option<PathBuf>,
+    pub hostname: Option<String>,
+}
+
+impl RedactionContext {
+    pub fn current() -> Self {
+        let hostname = std::process::Command::new("hostname")
+            .output()
+            .ok()
+            .filter(|output| output.status.success())
+            .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+            .or_else(|| std::env::var("HOSTNAME").ok())
+            .or_else(|| std::env::var("COMPUTERNAME").ok())
+            .filter(|hostname| !hostname.is_empty());
+        Self {
+            home_dir: dirs::home_dir(),
+            hostname,
+        }
+    }
+}
+
+struct Rule {
+    name: String,
+    kind: RedactionKind,
+    regex: Regex,
+    replacement: String,
+}
+
+/// Replaces secrets and personal details in session messages
+pub struct Redactor {
+    rules: Vec<Rule>,
+}
+
+impl Redactor {
+    pub fn new(options: &RedactionOptions, context: &RedactionContext) -> ManagerResult<Self> {
+        let mut rules = Vec::new();
+        let mut add = |name: &str, kind: RedactionKind, pattern: &str, replacement: String| {
+            Regex::new(pattern)
+                .map(|regex| {
+                    rules.push(Rule {
+                        name: name.to_string(),
+                        kind,
+                        regex,
+                        replacement,
+                    })
+                })
+                .map_err(|e| ManagerError::validation(format!("Invalid pattern {pattern:?}: {e}")))
+        };
+
+        if options.secrets {
+            for (name, pattern) in SECRET_RULES {
+                add(name, RedactionKind::Secret, pattern, placeholder(name))?;
+            }
+        }
+        for (i, pattern) in options.custom_patterns.iter().enumerate() {
+            let name = format!("custom[{i}]");
+            let replacement = placeholder("custom");
+            add(&name, RedactionKind::Custom, pattern, replacement)?;
+        }
+        if options.home_directory {
+            if let Some(home) = context.home_dir.as_ref().map(|home| home.to_string_lossy()) {
+                let home = home.trim_end_matches(['/', '\\']);
+                if !home.is_empty() {
+                    // Windows paths also show up with forward slashes
+                    let spellings = [home.to_string(), home.replace('\\', "/")];
+                    let alternatives: Vec<String> =
+                        spellings.iter().map(|home| regex::escape(home)).collect();
+                    let pattern = format!(r"(?:{})(?:\b|$)", alternatives.join("|"));
+                    add(
+                        "home_directory",
+                        RedactionKind::HomeDirectory,
+                        &pattern,
+                        "~".to_string(),
+                    )?;
+                }
+            }
+        }
+        if options.emails {
+            add(
+                "email",
+                RedactionKind::Email,
+                EMAIL_PATTERN,
+                placeholder("email"),
+            )?;
+        }
+        if options.hostnames {
+            if let Some(hostname) = context.hostname.as_deref().filter(|h| h.len() >= 3) {
+                let pattern = format!(r"(?i)\b{}\b", regex::escape(hostname));
+                add(
+                    "hostname",
+                    RedactionKind::Hostname,
+                    &pattern,
+                    placeholder("hostname"),
+                )?;
+            }
+            add(
+                "internal_host",
+                RedactionKind::Hostname,
+                INTERNAL_HOST_PATTERN,
+                placeholder("hostname"),
+            )?;
+        }
+
+        Ok(Self { rules })
+    }
+
+    /// Redact every text field of `messages` in place
+    pub fn redact_messages(&self, messages: &mut [ClaudeMessage]) -> RedactionReport {
+        let mut report = RedactionReport::default();
+        for (message_index, message) in messages.iter_mut().enumerate() {
+            let mut pass = Pass {
+                redactor: self,
+                message_index,
+                message_uuid: None,
+                report: &mut report,
+            };
+            match message {
+                ClaudeMessage::User {
+                    uuid,
+                    content,
+                    cwd,
+                    git_branch,
+                    ..
+                }
+                | ClaudeMessage::Assistant {
+                    uuid,
+                    content,
+                    cwd,
+                    git_branch,
+                    ..
+                } => {
+                    pass.message_uuid = Some(uuid.clone());
+                    pass.text(cwd, "cwd");
+                    if let Some(git_branch) = git_branch {
+                        pass.text(git_branch, "git_branch");
+                    }
+                    match content {
+                        MessageContent::User { content, .. } => pass.text(content, "content"),
+                        MessageContent::UserBlocks { content, .. }
+                        | MessageContent::Assistant { content, .. } => {
+                            pass.blocks(content, "content");
+                        }
+                    }
+                }
+                ClaudeMessage::Summary { summary, leaf_uuid } => {
+                    pass.message_uuid = Some(leaf_uuid.clone());
+                    pass.text(summary, "summary");
+                }
+            }
+        }
+        report
+    }
+
+    /// `text` with every match replaced, and the rules that matched
+    fn redact_text(&self, text: &str) -> Option<(String, Vec<&Rule>)> {
+        let mut current = text.to_string();
+        let mut matched = Vec::new();
+        for rule in &self.rules {
+            let mut redacted = String::with_capacity(current.len());
+            let mut last = 0;
+            for captures in rule.regex.captures_iter(&current) {
+                let Some(found) = captures.get(1).or_else(|| captures.get(0)) else {
+                    continue;
+                };
+                if found.as_str() == rule.replacement {
+                    continue;
+                }
+                redacted.push_str(&current[last..found.start()]);
+                redacted.push_str(&rule.replacement);
+                last = found.end();
+                matched.push(rule);
+            }
+            if last > 0 {
+                redacted.push_str(&current[last..]);
+                current = redacted;
+            }
+        }
+        (!matched.is_empty()).then_some((current, matched))
+    }
+}
+
+/// Walks one message, recording each redaction with where it was made
+struct Pass<'a> {
+    redactor: &'a Redactor,
+    message_index: usize,
+    message_uuid: Option<String>,
+    report: &'a mut RedactionReport,
+}
+
+impl Pass<'_> {
+    fn text(&mut self, text: &mut String, field: &str) {
+        let Some((redacted, matched)) = self.redactor.redact_text(text) else {
+            return;
+        };
+        *text = redacted;
+        for rule in matched {
+            *self.report.counts.entry(rule.name.clone()).or_insert(0) += 1;
+            self.report.redactions.push(Redaction {
+                kind: rule.kind,
+                rule: rule.name.clone(),
+                message_index: self.message_index,
+                message_uuid: self.message_uuid.clone(),
+                field: field.to_string(),
+                replacement: rule.replacement.clone(),
+            });
+        }
+    }
+
+    fn blocks(&mut self, blocks: &mut [ContentBlock], field: &str) {
+        for (i, block) in blocks.iter_mut().enumerate() {
+            let field = format!("{field}[{i}]");
+            match block {
+                ContentBlock::Text { text } => self.text(text, &format!("{field}.text")),
+                ContentBlock::ToolUse { input, result, .. } => {
+                    self.value(input, &format!("{field}.input"));
+                    if let Some(result) = result {
+                        self.blocks(&mut result.content, &format!("{field}.result.content"));
+                    }
+                }
+                ContentBlock::ToolResult { content, .. } => {
+                    self.blocks(content, &format!("{field}.content"));
+                }
+                ContentBlock::Thinking { thinking, .. } => {
+                    self.text(thinking, &format!("{field}.thinking"));
+                }
+                ContentBlock::Image {
+                    source: ImageSource::Url { url },
+                    ..
+                } => self.text(url, &format!("{field}.source.url")),
+                // Opaque payloads, not text a reader would see
+                ContentBlock::RedactedThinking { .. } | ContentBlock::Image { .. } => {}
+            }
+        }
+    }
+
+    fn value(&mut self, value: &mut Value, field: &str) {
+        match value {
+            Value::String(text) => self.text(text, field),
+            Value::Array(items) => {
+                for (i, item) in items.iter_mut().enumerate() {
+                    self.value(item, &format!("{field}[{i}]"));
+                }
+            }
+            Value::Object(map) => {
+                for (key, item) in map.iter_mut() {
+                    self.value(item, &format!("{field}.{key}"));
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn placeholder(name: &str) -> String {
+    format!("[REDACTED:{name}]")
+}
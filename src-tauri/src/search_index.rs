@@ -0,0 +1,558 @@
+use crate::jsonl_tail::TailCursor;
+use crate::models::{
+    ClaudeMessage, ContentBlock, MessageContent, MessageSearchHit, SnippetSegment,
+};
+use chrono::{DateTime, Utc};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+pub const DEFAULT_SEARCH_LIMIT: usize = 50;
+
+const BM25_K1: f64 = 1.2;
+const BM25_B: f64 = 0.75;
+
+/// Longer tokens are almost always encoded blobs and only bloat the index
+const MAX_TERM_BYTES: usize = 64;
+
+/// Characters of context kept around the first match in a snippet
+const SNIPPET_CHARS_BEFORE: usize = 60;
+const SNIPPET_CHARS: usize = 200;
+
+/// Removed documents are compacted away once there are this many and they
+/// outnumber the live ones
+const COMPACT_MIN_REMOVED: usize = 1024;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Token {
+    pub term: String,
+    /// Byte range of the token in the tokenized text
+    pub start: usize,
+    pub end: usize,
+}
+
+fn is_cjk(c: char) -> bool {
+    matches!(c as u32,
+        0x3040..=0x30FF // Hiragana, Katakana
+        | 0x3400..=0x4DBF // CJK Extension A
+        | 0x4E00..=0x9FFF // CJK Unified Ideographs
+        | 0xAC00..=0xD7AF // Hangul syllables
+        | 0xF900..=0xFAFF // CJK Compatibility Ideographs
+        | 0xFF66..=0xFF9F // Halfwidth Katakana
+    )
+}
+
+fn push_word(text: &str, start: usize, end: usize, tokens: &mut Vec<Token>) {
+    if end - start <= MAX_TERM_BYTES {
+        tokens.push(Token {
+            term: text[start..end].to_lowercase(),
+            start,
+            end,
+        });
+    }
+}
+
+/// Overlapping bigrams, since CJK text has no spaces between words
+fn push_cjk_run(text: &str, run: &[(usize, char)], tokens: &mut Vec<Token>) {
+    if let [(start, c)] = run {
+        push_word(text, *start, start + c.len_utf8(), tokens);
+        return;
+    }
+    for pair in run.windows(2) {
+        let (start, _) = pair[0];
+        let (last, c) = pair[1];
+        push_word(text, start, last + c.len_utf8(), tokens);
+    }
+}
+
+/// Split text into lowercase word terms and CJK bigrams with their byte ranges
+pub fn tokenize(text: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut word_start: Option<usize> = None;
+    let mut cjk_run: Vec<(usize, char)> = Vec::new();
+
+    for (index, c) in text.char_indices() {
+        if is_cjk(c) {
+            if let Some(start) = word_start.take() {
+                push_word(text, start, index, &mut tokens);
+            }
+            cjk_run.push((index, c));
+        } else {
+            if !cjk_run.is_empty() {
+                push_cjk_run(text, &cjk_run, &mut tokens);
+                cjk_run.clear();
+            }
+            if c.is_alphanumeric() || c == '_' {
+                word_start.get_or_insert(index);
+            } else if let Some(start) = word_start.take() {
+                push_word(text, start, index, &mut tokens);
+            }
+        }
+    }
+
+    if let Some(start) = word_start {
+        push_word(text, start, text.len(), &mut tokens);
+    }
+    if !cjk_run.is_empty() {
+        push_cjk_run(text, &cjk_run, &mut tokens);
+    }
+
+    tokens
+}
+
+fn collect_json_strings(value: &serde_json::Value, parts: &mut Vec<String>) {
+    match value {
+        serde_json::Value::String(s) => parts.push(s.clone()),
+        serde_json::Value::Array(items) => {
+            for item in items {
+                collect_json_strings(item, parts);
+            }
+        }
+        serde_json::Value::Object(map) => {
+            for item in map.values() {
+                collect_json_strings(item, parts);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn collect_block_text(block: &ContentBlock, parts: &mut Vec<String>) {
+    match block {
+        ContentBlock::Text { text } => parts.push(text.clone()),
+        ContentBlock::ToolUse { name, input, .. } => {
+            parts.push(name.clone());
+            collect_json_strings(input, parts);
+        }
+        ContentBlock::ToolResult { content, .. } => {
+            for block in content {
+                collect_block_text(block, parts);
+            }
+        }
+        // Thinking and images are not searchable
+        _ => {}
+    }
+}
+
+/// Searchable text of a message: user and assistant text, tool inputs and tool results
+pub fn searchable_text(message: &ClaudeMessage) -> String {
+    let content = match message {
+        ClaudeMessage::User { content, .. } | ClaudeMessage::Assistant { content, .. } => content,
+        ClaudeMessage::Summary { .. } => return String::new(),
+    };
+
+    let mut parts = Vec::new();
+    match content {
+        MessageContent::User { content, .. } => parts.push(content.clone()),
+        MessageContent::UserBlocks { content, .. } | MessageContent::Assistant { content, .. } => {
+            for block in content {
+                collect_block_text(block, &mut parts);
+            }
+        }
+    }
+
+    parts.retain(|part| !part.trim().is_empty());
+    parts.join("\n")
+}
+
+struct Document {
+    session_id: String,
+    project_path: String,
+    message_uuid: String,
+    message_type: &'static str,
+    timestamp: DateTime<Utc>,
+    /// The text is not kept; snippets read the message back from this line
+    file_path: Arc<Path>,
+    offset: u64,
+    length: usize,
+    terms: Vec<String>,
+}
+
+struct IndexedFile {
+    cursor: TailCursor,
+    documents: Vec<usize>,
+}
+
+/// A message parsed from a session file, with the byte offset of its line
+pub struct IndexedMessage {
+    pub offset: u64,
+    pub message: ClaudeMessage,
+}
+
+/// In-memory inverted index over session messages, fed incrementally per file
+#[derive(Default)]
+pub struct SearchIndex {
+    /// Removed documents leave a None so posting ids stay valid until the next compaction
+    documents: Vec<Option<Document>>,
+    /// term -> (document id, term frequency)
+    postings: HashMap<String, Vec<(usize, u32)>>,
+    files: HashMap<PathBuf, IndexedFile>,
+    live_documents: usize,
+    total_length: usize,
+}
+
+impl SearchIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Where indexing of `file_path` stopped, to resume with `jsonl_tail::read_appended_lines`
+    pub fn cursor(&self, file_path: &Path) -> Option<&TailCursor> {
+        self.files.get(file_path).map(|file| &file.cursor)
+    }
+
+    /// Add messages read from `file_path`; `reset` drops what was indexed for it before
+    pub fn update_file(
+        &mut self,
+        file_path: &Path,
+        project_path: &str,
+        reset: bool,
+        cursor: TailCursor,
+        messages: &[IndexedMessage],
+    ) {
+        if reset {
+            self.remove_file(file_path);
+            self.compact_if_sparse();
+        }
+
+        let shared_path: Arc<Path> = Arc::from(file_path);
+        let mut added = Vec::new();
+        for message in messages {
+            if let Some(id) = self.add_message(project_path, &shared_path, message) {
+                added.push(id);
+            }
+        }
+
+        let file = self
+            .files
+            .entry(file_path.to_path_buf())
+            .or_insert_with(|| IndexedFile {
+                cursor: TailCursor::default(),
+                documents: Vec::new(),
+            });
+        file.cursor = cursor;
+        file.documents.extend(added);
+    }
+
+    fn add_message(
+        &mut self,
+        project_path: &str,
+        file_path: &Arc<Path>,
+        indexed: &IndexedMessage,
+    ) -> Option<usize> {
+        let message = &indexed.message;
+        let (session_id, uuid, timestamp, message_type) = match message {
+            ClaudeMessage::User {
+                session_id,
+                uuid,
+                timestamp,
+                ..
+            } => (session_id, uuid, timestamp, "user"),
+            ClaudeMessage::Assistant {
+                session_id,
+                uuid,
+                timestamp,
+                ..
+            } => (session_id, uuid, timestamp, "assistant"),
+            ClaudeMessage::Summary { .. } => return None,
+        };
+
+        let text = searchable_text(message);
+        let tokens = tokenize(&text);
+        if tokens.is_empty() {
+            return None;
+        }
+
+        let mut frequencies: HashMap<String, u32> = HashMap::new();
+        for token in &tokens {
+            *frequencies.entry(token.term.clone()).or_default() += 1;
+        }
+
+        let id = self.documents.len();
+        for (term, frequency) in &frequencies {
+            self.postings
+                .entry(term.clone())
+                .or_default()
+                .push((id, *frequency));
+        }
+
+        self.live_documents += 1;
+        self.total_length += tokens.len();
+        self.documents.push(Some(Document {
+            session_id: session_id.clone(),
+            project_path: project_path.to_string(),
+            message_uuid: uuid.clone(),
+            message_type,
+            timestamp: *timestamp,
+            file_path: Arc::clone(file_path),
+            offset: indexed.offset,
+            length: tokens.len(),
+            terms: frequencies.into_keys().collect(),
+        }));
+        Some(id)
+    }
+
+    fn remove_file(&mut self, file_path: &Path) {
+        let Some(file) = self.files.remove(file_path) else {
+            return;
+        };
+
+        for id in file.documents {
+            let Some(document) = self.documents[id].take() else {
+                continue;
+            };
+            for term in &document.terms {
+                if let Some(postings) = self.postings.get_mut(term) {
+                    postings.retain(|(doc, _)| *doc != id);
+                    if postings.is_empty() {
+                        self.postings.remove(term);
+                    }
+                }
+            }
+            self.live_documents -= 1;
+            self.total_length -= document.length;
+        }
+    }
+
+    /// Drop files that no longer exist
+    pub fn retain(&mut self, seen: &HashSet<PathBuf>) {
+        let removed: Vec<PathBuf> = self
+            .files
+            .keys()
+            .filter(|path| !seen.contains(*path))
+            .cloned()
+            .collect();
+        for path in removed {
+            self.remove_file(&path);
+        }
+        self.compact_if_sparse();
+    }
+
+    /// Drop the slots of removed documents and renumber the rest
+    fn compact_if_sparse(&mut self) {
+        let removed = self.documents.len() - self.live_documents;
+        if removed < COMPACT_MIN_REMOVED || removed <= self.live_documents {
+            return;
+        }
+
+        let mut new_ids = vec![None; self.documents.len()];
+        let mut documents = Vec::with_capacity(self.live_documents);
+        for (old_id, document) in std::mem::take(&mut self.documents).into_iter().enumerate() {
+            if let Some(document) = document {
+                new_ids[old_id] = Some(documents.len());
+                documents.push(Some(document));
+            }
+        }
+        self.documents = documents;
+
+        for postings in self.postings.values_mut() {
+            for (id, _) in postings.iter_mut() {
+                // Postings of removed documents were already dropped
+                *id = new_ids[*id].expect("posting points at a removed document");
+            }
+        }
+        for file in self.files.values_mut() {
+            for id in file.documents.iter_mut() {
+                *id = new_ids[*id].expect("file lists a removed document");
+            }
+        }
+    }
+
+    /// Number of document slots, removed ones included
+    #[cfg(test)]
+    pub fn slot_count(&self) -> usize {
+        self.documents.len()
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    fn query_terms(query: &str) -> Vec<String> {
+        let mut terms: Vec<String> = tokenize(query).into_iter().map(|t| t.term).collect();
+        terms.sort();
+        terms.dedup();
+        terms
+    }
+
+    /// BM25 scores of the documents that contain every term
+    fn score_documents(&self, terms: &[String]) -> HashMap<usize, f64> {
+        if terms.is_empty() || self.live_documents == 0 {
+            return HashMap::new();
+        }
+
+        let mut term_postings = Vec::new();
+        for term in terms {
+            match self.postings.get(term) {
+                Some(postings) => term_postings.push(postings),
+                None => return HashMap::new(),
+            }
+        }
+        // Intersect starting from the rarest term
+        term_postings.sort_by_key(|postings| postings.len());
+
+        let document_count = self.live_documents as f64;
+        let average_length = self.total_length as f64 / document_count;
+        let mut scores: HashMap<usize, f64> = HashMap::new();
+        for (position, postings) in term_postings.iter().enumerate() {
+            let df = postings.len() as f64;
+            let idf = ((document_count - df + 0.5) / (df + 0.5) + 1.0).ln();
+            let mut next_scores = HashMap::new();
+            for (id, frequency) in postings.iter() {
+                let previous = match scores.get(id) {
+                    Some(score) => *score,
+                    None if position == 0 => 0.0,
+                    None => continue,
+                };
+                let Some(document) = &self.documents[*id] else {
+                    continue;
+                };
+                let tf = *frequency as f64;
+                let norm = 1.0 - BM25_B + BM25_B * document.length as f64 / average_length;
+                let score = idf * tf * (BM25_K1 + 1.0) / (tf + BM25_K1 * norm);
+                next_scores.insert(*id, previous + score);
+            }
+            scores = next_scores;
+        }
+
+        scores
+    }
+
+    /// Ids of sessions with at least one message containing every query term
+    pub fn matching_sessions(&self, query: &str) -> HashSet<String> {
+        self.score_documents(&Self::query_terms(query))
+            .into_keys()
+            .filter_map(|id| self.documents[id].as_ref())
+            .map(|document| document.session_id.clone())
+            .collect()
+    }
+
+    /// Messages containing every query term, ranked by BM25 and then by recency
+    ///
+    /// `read_text` returns the searchable text of the message on the line at
+    /// a byte offset of a session file, for the snippets of the returned hits.
+    pub fn search<F>(&self, query: &str, limit: usize, read_text: F) -> Vec<MessageSearchHit>
+    where
+        F: Fn(&Path, u64, &str) -> Option<String>,
+    {
+        let terms = Self::query_terms(query);
+        let scores = self.score_documents(&terms);
+
+        let mut ranked: Vec<(usize, f64)> = scores.into_iter().collect();
+        ranked.sort_by(|(a_id, a_score), (b_id, b_score)| {
+            b_score.total_cmp(a_score).then_with(|| {
+                let a_time = self.documents[*a_id].as_ref().map(|d| d.timestamp);
+                let b_time = self.documents[*b_id].as_ref().map(|d| d.timestamp);
+                b_time.cmp(&a_time)
+            })
+        });
+
+        let query_terms: HashSet<&str> = terms.iter().map(String::as_str).collect();
+        ranked
+            .into_iter()
+            .take(limit)
+            .filter_map(|(id, score)| {
+                let document = self.documents[id].as_ref()?;
+                let text = read_text(&document.file_path, document.offset, &document.session_id)
+                    .unwrap_or_default();
+                Some(MessageSearchHit {
+                    session_id: document.session_id.clone(),
+                    project_path: document.project_path.clone(),
+                    message_uuid: document.message_uuid.clone(),
+                    message_type: document.message_type.to_string(),
+                    timestamp: document.timestamp,
+                    score,
+                    snippet: build_snippet(&text, &query_terms),
+                })
+            })
+            .collect()
+    }
+}
+
+/// Byte offset `chars` characters before `index`, or after it when `forward`
+fn shift_chars(text: &str, index: usize, chars: usize, forward: bool) -> usize {
+    if forward {
+        text[index..]
+            .char_indices()
+            .nth(chars)
+            .map(|(offset, _)| index + offset)
+            .unwrap_or(text.len())
+    } else {
+        text[..index]
+            .char_indices()
+            .rev()
+            .nth(chars.saturating_sub(1))
+            .map(|(offset, _)| offset)
+            .unwrap_or(0)
+    }
+}
+
+fn push_segment(segments: &mut Vec<SnippetSegment>, text: &str, highlighted: bool) {
+    // Snippets are shown inline, so line breaks and indentation collapse to single spaces
+    let mut collapsed = String::with_capacity(text.len());
+    for c in text.chars() {
+        if !c.is_whitespace() {
+            collapsed.push(c);
+        } else if !collapsed.ends_with(' ') {
+            collapsed.push(' ');
+        }
+    }
+    if collapsed.is_empty() {
+        return;
+    }
+
+    match segments.last_mut() {
+        Some(last) if last.highlighted == highlighted => last.text.push_str(&collapsed),
+        _ => segments.push(SnippetSegment {
+            text: collapsed,
+            highlighted,
+        }),
+    }
+}
+
+/// Window of `text` around the first match, split into plain and highlighted segments
+pub fn build_snippet(text: &str, query_terms: &HashSet<&str>) -> Vec<SnippetSegment> {
+    let matches: Vec<Token> = tokenize(text)
+        .into_iter()
+        .filter(|token| query_terms.contains(token.term.as_str()))
+        .collect();
+
+    let first_match = matches.first().map(|token| token.start).unwrap_or(0);
+    let start = if first_match == 0 {
+        0
+    } else {
+        shift_chars(text, first_match, SNIPPET_CHARS_BEFORE, false)
+    };
+    let end = shift_chars(text, start, SNIPPET_CHARS, true);
+
+    // Merge overlapping matches (CJK bigrams overlap by one character)
+    let mut ranges: Vec<(usize, usize)> = Vec::new();
+    for token in matches
+        .iter()
+        .filter(|token| token.start >= start && token.end <= end)
+    {
+        match ranges.last_mut() {
+            Some((_, last_end)) if token.start <= *last_end => {
+                *last_end = (*last_end).max(token.end);
+            }
+            _ => ranges.push((token.start, token.end)),
+        }
+    }
+
+    let mut segments = Vec::new();
+    if start > 0 {
+        push_segment(&mut segments, "…", false);
+    }
+    let mut position = start;
+    for (match_start, match_end) in ranges {
+        push_segment(&mut segments, &text[position..match_start], false);
+        push_segment(&mut segments, &text[match_start..match_end], true);
+        position = match_end;
+    }
+    push_segment(&mut segments, &text[position..end], false);
+    if end < text.len() {
+        push_segment(&mut segments, "…", false);
+    }
+
+    segments
+}
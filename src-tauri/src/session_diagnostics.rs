@@ -0,0 +1,145 @@
+use crate::jsonl_tail::{self, TailLine};
+use crate::models::{SessionDiagnostics, SessionHealth, SessionLineDiagnostic, SessionLineIssue};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::Path;
+
+/// Entry types Claude Code writes to session files
+///
+/// Only `user`, `assistant` and `summary` entries become messages; the rest
+/// carry metadata and are skipped on purpose.
+pub const KNOWN_ENTRY_TYPES: &[&str] = &[
+    "user",
+    "assistant",
+    "summary",
+    "system",
+    "file-history-snapshot",
+    "queue-operation",
+];
+
+/// Characters of the raw line kept in a diagnostic
+const EXCERPT_CHARS: usize = 200;
+
+/// Parse a session file line into its JSON object
+///
+/// Blank lines yield `Ok(None)`. Lines that are not a JSON object are
+/// returned as a diagnostic so callers can count them instead of dropping them.
+pub fn parse_line(line: &TailLine) -> Result<Option<Value>, SessionLineDiagnostic> {
+    if line.text.trim().is_empty() {
+        return Ok(None);
+    }
+    match serde_json::from_str::<Value>(&line.text) {
+        Ok(entry) if entry.is_object() => Ok(Some(entry)),
+        Ok(_) => Err(diagnostic(
+            line,
+            SessionLineIssue::NotAnObject,
+            "Line is valid JSON but not an object".to_string(),
+        )),
+        Err(e) => Err(diagnostic(
+            line,
+            SessionLineIssue::InvalidJson,
+            format!("Invalid JSON: {e}"),
+        )),
+    }
+}
+
+/// Report an entry whose `type` is missing or not one this app knows
+pub fn check_entry_type(line: &TailLine, entry: &Value) -> Option<SessionLineDiagnostic> {
+    match entry.get("type").and_then(|t| t.as_str()) {
+        Some(entry_type) if KNOWN_ENTRY_TYPES.contains(&entry_type) => None,
+        Some(entry_type) => Some(diagnostic(
+            line,
+            SessionLineIssue::UnknownType,
+            format!("Unknown entry type {entry_type:?}"),
+        )),
+        None => Some(diagnostic(
+            line,
+            SessionLineIssue::MissingType,
+            "Entry has no \"type\"".to_string(),
+        )),
+    }
+}
+
+pub fn health(corrupted_lines: usize, unrecognized_lines: usize) -> SessionHealth {
+    if corrupted_lines > 0 {
+        SessionHealth::Corrupted
+    } else if unrecognized_lines > 0 {
+        SessionHealth::Unrecognized
+    } else {
+        SessionHealth::Healthy
+    }
+}
+
+/// Every line of the session file at `path` that was skipped or not understood
+pub fn diagnose_file(session_id: &str, path: &Path) -> io::Result<SessionDiagnostics> {
+    let tail = jsonl_tail::read_appended_lines(path, None)?;
+    let mut lines = Vec::new();
+    let mut unknown_types = BTreeMap::new();
+    let mut corrupted_lines = 0;
+    let mut unrecognized_lines = 0;
+
+    for line in &tail.lines {
+        match parse_line(line) {
+            Ok(Some(entry)) => {
+                if let Some(found) = check_entry_type(line, &entry) {
+                    if let Some(entry_type) = entry.get("type").and_then(|t| t.as_str()) {
+                        *unknown_types.entry(entry_type.to_string()).or_insert(0) += 1;
+                    }
+                    unrecognized_lines += 1;
+                    lines.push(found);
+                }
+            }
+            Ok(None) => {}
+            Err(found) => {
+                corrupted_lines += 1;
+                lines.push(found);
+            }
+        }
+    }
+
+    // The tail reader holds back a last line that is not complete JSON yet.
+    // It may still be being written, so it does not change the health.
+    let mut file = File::open(path)?;
+    let mut rest = Vec::new();
+    file.seek(SeekFrom::Start(tail.cursor.offset))?;
+    file.read_to_end(&mut rest)?;
+    let rest = String::from_utf8_lossy(&rest);
+    let truncated = !rest.trim().is_empty();
+    if truncated {
+        let line = TailLine {
+            number: tail.cursor.line_count + 1,
+            offset: tail.cursor.offset,
+            text: rest.trim_end_matches(['\n', '\r']).to_string(),
+        };
+        lines.push(diagnostic(
+            &line,
+            SessionLineIssue::Truncated,
+            "Last line is incomplete".to_string(),
+        ));
+    }
+
+    Ok(SessionDiagnostics {
+        session_id: session_id.to_string(),
+        file_path: path.to_string_lossy().to_string(),
+        line_count: tail.cursor.line_count + usize::from(truncated),
+        health: health(corrupted_lines, unrecognized_lines),
+        lines,
+        unknown_types,
+    })
+}
+
+fn diagnostic(line: &TailLine, issue: SessionLineIssue, message: String) -> SessionLineDiagnostic {
+    let mut excerpt: String = line.text.chars().take(EXCERPT_CHARS).collect();
+    if excerpt.len() < line.text.len() {
+        excerpt.push('…');
+    }
+    SessionLineDiagnostic {
+        line: line.number,
+        offset: line.offset,
+        issue,
+        message,
+        excerpt,
+    }
+}
@@ -0,0 +1,177 @@
+use crate::error::{ManagerResult, PathContext};
+use crate::file_store;
+use crate::jsonl_tail::TailCursor;
+use crate::models::{ClaudeSession, TokenUsage};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// File name of the persistent index in the app data directory
+pub const SESSION_INDEX_FILE: &str = "session_index.json";
+
+/// Bump when `ClaudeSession` or `SessionScan` changes shape so stale indexes are rebuilt
+const INDEX_VERSION: u32 = 4;
+
+/// Summary parser state, kept so appended lines can be folded in without re-reading the file
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SessionScan {
+    pub cursor: TailCursor,
+    pub message_count: usize,
+    pub first_timestamp: Option<DateTime<Utc>>,
+    pub latest_timestamp: Option<DateTime<Utc>>,
+    pub latest_content_preview: Option<String>,
+    pub git_branch: Option<String>,
+    pub actual_project_path: Option<String>,
+    pub has_incomplete_sequence: bool,
+    /// Streamed responses repeat the same usage on every line, so keep one entry per request
+    pub usage_by_request: HashMap<String, RequestUsage>,
+    /// Lines that are not JSON objects
+    pub corrupted_lines: usize,
+    /// Entries with a missing or unknown `type`
+    pub unrecognized_lines: usize,
+}
+
+/// Token usage of one API request, with what the cost report groups it by
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RequestUsage {
+    pub timestamp: Option<DateTime<Utc>>,
+    pub model: Option<String>,
+    pub usage: TokenUsage,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct IndexEntry {
+    size: u64,
+    modified: DateTime<Utc>,
+    session: ClaudeSession,
+    scan: SessionScan,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct IndexFile {
+    version: u32,
+    entries: HashMap<PathBuf, IndexEntry>,
+}
+
+/// Session summaries keyed by file path, reused while size and mtime are unchanged
+pub struct SessionIndex {
+    path: Option<PathBuf>,
+    entries: HashMap<PathBuf, IndexEntry>,
+    dirty: bool,
+}
+
+impl SessionIndex {
+    /// Index that lives only for this process
+    pub fn in_memory() -> Self {
+        Self {
+            path: None,
+            entries: HashMap::new(),
+            dirty: false,
+        }
+    }
+
+    /// Load the index from disk, starting empty if it is missing, corrupted or outdated
+    pub fn load(path: PathBuf) -> Self {
+        let entries = fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str::<IndexFile>(&content).ok())
+            .filter(|index| index.version == INDEX_VERSION)
+            .map(|index| index.entries);
+
+        Self {
+            path: Some(path),
+            // A file we could not use gets overwritten on the next save
+            dirty: entries.is_none(),
+            entries: entries.unwrap_or_default(),
+        }
+    }
+
+    pub fn get(
+        &self,
+        file_path: &Path,
+        size: u64,
+        modified: DateTime<Utc>,
+    ) -> Option<&ClaudeSession> {
+        self.entries
+            .get(file_path)
+            .filter(|entry| entry.size == size && entry.modified == modified)
+            .map(|entry| &entry.session)
+    }
+
+    /// Parser state of the last scan, used to resume when the file has grown
+    pub fn get_scan(&self, file_path: &Path) -> Option<&SessionScan> {
+        self.entries.get(file_path).map(|entry| &entry.scan)
+    }
+
+    pub fn insert(
+        &mut self,
+        file_path: &Path,
+        size: u64,
+        modified: DateTime<Utc>,
+        session: ClaudeSession,
+        scan: SessionScan,
+    ) {
+        self.entries.insert(
+            file_path.to_path_buf(),
+            IndexEntry {
+                size,
+                modified,
+                session,
+                scan,
+            },
+        );
+        self.dirty = true;
+    }
+
+    /// Summary and parser state of every indexed session file
+    pub fn entries(&self) -> impl Iterator<Item = (&ClaudeSession, &SessionScan)> {
+        self.entries
+            .values()
+            .map(|entry| (&entry.session, &entry.scan))
+    }
+
+    /// Path, summary and parser state of every indexed session file
+    pub fn files(&self) -> impl Iterator<Item = (&Path, &ClaudeSession, &SessionScan)> {
+        self.entries
+            .iter()
+            .map(|(path, entry)| (path.as_path(), &entry.session, &entry.scan))
+    }
+
+    /// Drop entries for files that no longer exist
+    pub fn retain(&mut self, seen: &HashSet<PathBuf>) {
+        let before = self.entries.len();
+        self.entries.retain(|path, _| seen.contains(path));
+        if self.entries.len() != before {
+            self.dirty = true;
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.dirty = true;
+    }
+
+    /// Write the index if anything changed, via a temp file so a crash cannot corrupt it
+    pub fn save(&mut self) -> ManagerResult<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        if !self.dirty {
+            return Ok(());
+        }
+
+        let index = IndexFile {
+            version: INDEX_VERSION,
+            entries: std::mem::take(&mut self.entries),
+        };
+        let serialized = serde_json::to_string(&index);
+        self.entries = index.entries;
+
+        file_store::write_atomic(path, serialized?.as_bytes()).at_path(path)?;
+
+        self.dirty = false;
+        Ok(())
+    }
+}
@@ -0,0 +1,189 @@
+use crate::models::{SettingOrigin, SettingsLayer, SettingsScope};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Where administrators put settings that override everyone else's
+pub fn default_managed_settings_path() -> PathBuf {
+    if cfg!(target_os = "macos") {
+        PathBuf::from("/Library/Application Support/ClaudeCode/managed-settings.json")
+    } else if cfg!(windows) {
+        PathBuf::from(r"C:\ProgramData\ClaudeCode\managed-settings.json")
+    } else {
+        PathBuf::from("/etc/claude-code/managed-settings.json")
+    }
+}
+
+/// Settings files that apply in `project_path`, from the highest precedence down
+pub fn layer_paths(
+    managed_settings: Option<&Path>,
+    claude_dir: &Path,
+    project_path: Option<&Path>,
+) -> Vec<(SettingsScope, PathBuf)> {
+    let mut layers = Vec::new();
+    if let Some(managed) = managed_settings {
+        layers.push((SettingsScope::Enterprise, managed.to_path_buf()));
+    }
+    if let Some(project) = project_path {
+        let project_dir = project.join(".claude");
+        layers.push((
+            SettingsScope::ProjectLocal,
+            project_dir.join("settings.local.json"),
+        ));
+        layers.push((SettingsScope::Project, project_dir.join("settings.json")));
+    }
+    layers.push((SettingsScope::User, claude_dir.join("settings.json")));
+    layers
+}
+
+/// Read each layer; missing files and files that fail to parse are reported, not fatal
+pub fn load_layers(
+    paths: Vec<(SettingsScope, PathBuf)>,
+) -> Vec<(SettingsLayer, Option<serde_json::Value>)> {
+    paths
+        .into_iter()
+        .map(|(scope, path)| {
+            let mut layer = SettingsLayer {
+                scope,
+                path: path.to_string_lossy().to_string(),
+                exists: path.exists(),
+                error: None,
+            };
+            if !layer.exists {
+                return (layer, None);
+            }
+
+            let parsed = fs::read_to_string(&path)
+                .map_err(|e| e.to_string())
+                .and_then(|content| {
+                    serde_json::from_str::<serde_json::Value>(&content).map_err(|e| e.to_string())
+                })
+                .and_then(|value| match value {
+                    serde_json::Value::Object(_) => Ok(value),
+                    _ => Err("Settings file is not a JSON object".to_string()),
+                });
+            match parsed {
+                Ok(value) => (layer, Some(value)),
+                Err(error) => {
+                    layer.error = Some(error);
+                    (layer, None)
+                }
+            }
+        })
+        .collect()
+}
+
+/// `parent.key`, or `key` at the top level
+pub fn child_key(parent: &str, key: &str) -> String {
+    if parent.is_empty() {
+        key.to_string()
+    } else {
+        format!("{parent}.{key}")
+    }
+}
+
+/// Record every value in `value`: scalars, and each item of an array
+fn record_leaves(
+    key: &str,
+    value: &serde_json::Value,
+    layer: &SettingsLayer,
+    out: &mut Vec<SettingOrigin>,
+) {
+    match value {
+        serde_json::Value::Object(map) => {
+            for (child, value) in map {
+                record_leaves(&child_key(key, child), value, layer, out);
+            }
+        }
+        serde_json::Value::Array(items) => {
+            for item in items {
+                out.push(SettingOrigin {
+                    key: key.to_string(),
+                    value: item.clone(),
+                    scope: layer.scope,
+                    path: layer.path.clone(),
+                });
+            }
+        }
+        value => out.push(SettingOrigin {
+            key: key.to_string(),
+            value: value.clone(),
+            scope: layer.scope,
+            path: layer.path.clone(),
+        }),
+    }
+}
+
+/// Merged settings with the origin of every value
+pub struct MergedSettings {
+    pub settings: serde_json::Value,
+    pub origins: Vec<SettingOrigin>,
+    /// Values hidden by the same key in a file with higher precedence
+    pub overridden: Vec<SettingOrigin>,
+}
+
+struct Merger {
+    origins: Vec<SettingOrigin>,
+    overridden: Vec<SettingOrigin>,
+}
+
+impl Merger {
+    /// Merge a lower-precedence `incoming` value into what is already there
+    fn merge(
+        &mut self,
+        key: &str,
+        target: &mut serde_json::Value,
+        incoming: &serde_json::Value,
+        layer: &SettingsLayer,
+    ) {
+        match (target, incoming) {
+            (serde_json::Value::Object(target), serde_json::Value::Object(incoming)) => {
+                for (child, value) in incoming {
+                    let child_key = child_key(key, child);
+                    match target.get_mut(child) {
+                        Some(existing) => self.merge(&child_key, existing, value, layer),
+                        None => {
+                            record_leaves(&child_key, value, layer, &mut self.origins);
+                            target.insert(child.clone(), value.clone());
+                        }
+                    }
+                }
+            }
+            // Lists such as permission rules and hooks add up across files
+            (serde_json::Value::Array(target), serde_json::Value::Array(incoming)) => {
+                for item in incoming {
+                    if !target.contains(item) {
+                        target.push(item.clone());
+                        self.origins.push(SettingOrigin {
+                            key: key.to_string(),
+                            value: item.clone(),
+                            scope: layer.scope,
+                            path: layer.path.clone(),
+                        });
+                    }
+                }
+            }
+            _ => record_leaves(key, incoming, layer, &mut self.overridden),
+        }
+    }
+}
+
+/// Merge layers given from the highest precedence down
+///
+/// A value in a higher layer wins over the same key below it, objects merge key
+/// by key, and arrays are concatenated without duplicates.
+pub fn merge_layers(layers: &[(SettingsLayer, serde_json::Value)]) -> MergedSettings {
+    let mut merger = Merger {
+        origins: Vec::new(),
+        overridden: Vec::new(),
+    };
+    let mut settings = serde_json::Value::Object(serde_json::Map::new());
+    for (layer, value) in layers {
+        merger.merge("", &mut settings, value, layer);
+    }
+
+    MergedSettings {
+        settings,
+        origins: merger.origins,
+        overridden: merger.overridden,
+    }
+}
@@ -0,0 +1,532 @@
+//! Checks settings files against what Claude Code accepts before they are saved
+//!
+//! This is not JSON Schema validation. The known keys and their types are a
+//! hand-maintained table mirroring Claude Code's published settings schema
+//! (`https://json.schemastore.org/claude-code-settings.json`), so a key added
+//! upstream shows up as an unknown-key warning until `SETTINGS_KEYS` is updated.
+//! Unknown keys are only warnings for that reason. Permission rules and hook
+//! commands get checks a schema cannot express.
+
+use crate::command_analytics::{is_env_assignment, shell_words};
+use crate::hook_runner::HOOK_EVENTS;
+use crate::models::{
+    ClaudeSettings, DiagnosticSeverity, SettingsDiagnostic, SettingsDiagnosticCode,
+};
+use crate::permissions::PermissionRule;
+use crate::settings_layers::child_key;
+use std::collections::HashMap;
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+
+/// JSON type a known settings key must have
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Expected {
+    String,
+    Bool,
+    Number,
+    Object,
+    StringArray,
+    /// An object whose values are all strings, like `env`
+    StringMap,
+}
+
+impl Expected {
+    fn describe(self) -> &'static str {
+        match self {
+            Expected::String => "a string",
+            Expected::Bool => "true or false",
+            Expected::Number => "a number",
+            Expected::Object => "an object",
+            Expected::StringArray => "an array of strings",
+            Expected::StringMap => "an object of strings",
+        }
+    }
+
+    fn accepts(self, value: &serde_json::Value) -> bool {
+        match self {
+            Expected::String => value.is_string(),
+            Expected::Bool => value.is_boolean(),
+            Expected::Number => value.is_number(),
+            Expected::Object => value.is_object(),
+            Expected::StringArray => value
+                .as_array()
+                .is_some_and(|items| items.iter().all(|item| item.is_string())),
+            Expected::StringMap => value
+                .as_object()
+                .is_some_and(|map| map.values().all(|item| item.is_string())),
+        }
+    }
+}
+
+/// Top-level keys Claude Code reads from settings files
+///
+/// Keep in step with the published settings schema when Claude Code adds keys.
+const SETTINGS_KEYS: &[(&str, Expected)] = &[
+    ("$schema", Expected::String),
+    ("alwaysThinkingEnabled", Expected::Bool),
+    ("apiKeyHelper", Expected::String),
+    ("awsAuthRefresh", Expected::String),
+    ("awsCredentialExport", Expected::String),
+    ("cleanupPeriodDays", Expected::Number),
+    ("companyAnnouncements", Expected::StringArray),
+    ("disableAllHooks", Expected::Bool),
+    ("disabledMcpjsonServers", Expected::StringArray),
+    ("enableAllProjectMcpServers", Expected::Bool),
+    ("enabledMcpjsonServers", Expected::StringArray),
+    ("env", Expected::StringMap),
+    ("forceLoginMethod", Expected::String),
+    ("forceLoginOrgUUID", Expected::String),
+    ("hooks", Expected::Object),
+    ("includeCoAuthoredBy", Expected::Bool),
+    ("model", Expected::String),
+    ("otelHeadersHelper", Expected::String),
+    ("outputStyle", Expected::String),
+    ("permissions", Expected::Object),
+    ("spinnerTipsEnabled", Expected::Bool),
+    ("statusLine", Expected::Object),
+];
+
+const PERMISSION_KEYS: &[(&str, Expected)] = &[
+    ("additionalDirectories", Expected::StringArray),
+    ("allow", Expected::StringArray),
+    ("ask", Expected::StringArray),
+    ("defaultMode", Expected::String),
+    ("deny", Expected::StringArray),
+    ("disableBypassPermissionsMode", Expected::String),
+];
+
+const PERMISSION_MODES: &[&str] = &["default", "acceptEdits", "plan", "bypassPermissions"];
+
+/// Keys older Claude Code versions read, and what replaced them
+const DEPRECATED_KEYS: &[(&str, &str)] = &[
+    ("allowedTools", "use permissions.allow instead"),
+    (
+        "ignorePatterns",
+        "use Read(...) rules in permissions.deny instead",
+    ),
+];
+
+/// Shell builtins a hook may start with that are not files on PATH
+const SHELL_BUILTINS: &[&str] = &[
+    ".", ":", "[", "cd", "echo", "exit", "export", "false", "printf", "source", "test", "true",
+];
+
+/// 1-based line and column of every key in a JSON document
+///
+/// Object members point at their key and array items at their value, both
+/// under dotted keys such as `permissions.allow[1]`.
+struct PositionScanner<'a> {
+    chars: std::iter::Peekable<std::str::Chars<'a>>,
+    line: usize,
+    column: usize,
+    positions: HashMap<String, (usize, usize)>,
+}
+
+impl<'a> PositionScanner<'a> {
+    fn scan(content: &'a str) -> HashMap<String, (usize, usize)> {
+        let mut scanner = Self {
+            chars: content.chars().peekable(),
+            line: 1,
+            column: 1,
+            positions: HashMap::new(),
+        };
+        scanner.value("");
+        scanner.positions
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.chars.next()?;
+        if c == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+        Some(c)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.chars.peek().is_some_and(|c| c.is_whitespace()) {
+            self.bump();
+        }
+    }
+
+    fn string(&mut self) -> String {
+        let mut text = String::new();
+        self.bump();
+        while let Some(c) = self.bump() {
+            match c {
+                '"' => break,
+                '\\' => {
+                    if let Some(escaped) = self.bump() {
+                        text.push(escaped);
+                    }
+                }
+                c => text.push(c),
+            }
+        }
+        text
+    }
+
+    fn value(&mut self, key: &str) {
+        self.skip_whitespace();
+        match self.chars.peek() {
+            Some('{') => {
+                self.bump();
+                loop {
+                    self.skip_whitespace();
+                    if self.chars.peek() != Some(&'"') {
+                        break;
+                    }
+                    let position = (self.line, self.column);
+                    let child = child_key(key, &self.string());
+                    self.positions.insert(child.clone(), position);
+                    self.skip_whitespace();
+                    self.bump(); // ':'
+                    self.value(&child);
+                    self.skip_whitespace();
+                    if self.chars.peek() == Some(&',') {
+                        self.bump();
+                    }
+                }
+                self.bump(); // '}'
+            }
+            Some('[') => {
+                self.bump();
+                let mut index = 0;
+                loop {
+                    self.skip_whitespace();
+                    if matches!(self.chars.peek(), Some(']') | None) {
+                        break;
+                    }
+                    let item = format!("{key}[{index}]");
+                    self.positions
+                        .insert(item.clone(), (self.line, self.column));
+                    self.value(&item);
+                    self.skip_whitespace();
+                    if self.chars.peek() == Some(&',') {
+                        self.bump();
+                    }
+                    index += 1;
+                }
+                self.bump(); // ']'
+            }
+            Some('"') => {
+                self.string();
+            }
+            _ => {
+                while self
+                    .chars
+                    .peek()
+                    .is_some_and(|c| !matches!(c, ',' | '}' | ']') && !c.is_whitespace())
+                {
+                    self.bump();
+                }
+            }
+        }
+    }
+}
+
+/// Collects diagnostics, placing each at the position of its key
+struct Diagnostics {
+    positions: HashMap<String, (usize, usize)>,
+    found: Vec<SettingsDiagnostic>,
+}
+
+impl Diagnostics {
+    fn push(
+        &mut self,
+        severity: DiagnosticSeverity,
+        code: SettingsDiagnosticCode,
+        key: &str,
+        message: String,
+    ) {
+        let position = self.positions.get(key);
+        self.found.push(SettingsDiagnostic {
+            severity,
+            code,
+            key: Some(key.to_string()),
+            message,
+            line: position.map(|(line, _)| *line),
+            column: position.map(|(_, column)| *column),
+        });
+    }
+
+    fn error(&mut self, code: SettingsDiagnosticCode, key: &str, message: String) {
+        self.push(DiagnosticSeverity::Error, code, key, message);
+    }
+
+    fn warning(&mut self, code: SettingsDiagnosticCode, key: &str, message: String) {
+        self.push(DiagnosticSeverity::Warning, code, key, message);
+    }
+
+    /// Check the keys of `object` against `known`, warning about keys it does not list
+    fn check_keys(
+        &mut self,
+        parent: &str,
+        object: &serde_json::Map<String, serde_json::Value>,
+        known: &[(&str, Expected)],
+    ) {
+        for (name, value) in object {
+            let key = child_key(parent, name);
+            match known.iter().find(|(known, _)| known == name) {
+                Some((_, expected)) if !expected.accepts(value) => self.error(
+                    SettingsDiagnosticCode::InvalidType,
+                    &key,
+                    format!("`{key}` must be {}", expected.describe()),
+                ),
+                Some(_) => {}
+                None => match DEPRECATED_KEYS.iter().find(|(old, _)| old == name) {
+                    Some((_, replacement)) => self.warning(
+                        SettingsDiagnosticCode::DeprecatedKey,
+                        &key,
+                        format!("`{key}` is deprecated; {replacement}"),
+                    ),
+                    None => self.warning(
+                        SettingsDiagnosticCode::UnknownKey,
+                        &key,
+                        format!("Unknown setting `{key}`"),
+                    ),
+                },
+            }
+        }
+    }
+}
+
+/// Check a settings file before it is written
+///
+/// Syntax errors and values Claude Code cannot load are errors. Unknown or
+/// deprecated keys, rules that do not parse and hook commands that cannot be
+/// found on `path_var` are warnings.
+///
+/// Hooks run from the project directory, so relative hook paths are checked
+/// against `project_dir` and skipped when the settings apply to every project.
+pub fn validate_settings(
+    content: &str,
+    path_var: Option<&OsStr>,
+    project_dir: Option<&Path>,
+) -> Vec<SettingsDiagnostic> {
+    let value = match serde_json::from_str::<serde_json::Value>(content) {
+        Ok(value) => value,
+        Err(e) => {
+            return vec![SettingsDiagnostic {
+                severity: DiagnosticSeverity::Error,
+                code: SettingsDiagnosticCode::InvalidJson,
+                key: None,
+                message: e.to_string(),
+                line: Some(e.line()),
+                column: Some(e.column()),
+            }]
+        }
+    };
+    let Some(root) = value.as_object() else {
+        return vec![SettingsDiagnostic {
+            severity: DiagnosticSeverity::Error,
+            code: SettingsDiagnosticCode::InvalidType,
+            key: None,
+            message: "Settings must be a JSON object".to_string(),
+            line: Some(1),
+            column: Some(1),
+        }];
+    };
+
+    let mut diagnostics = Diagnostics {
+        positions: PositionScanner::scan(content),
+        found: Vec::new(),
+    };
+    diagnostics.check_keys("", root, SETTINGS_KEYS);
+    if let Some(permissions) = root.get("permissions").and_then(|p| p.as_object()) {
+        check_permissions(&mut diagnostics, permissions);
+    }
+    if let Some(hooks) = root.get("hooks").and_then(|h| h.as_object()) {
+        check_hooks(&mut diagnostics, hooks, path_var, project_dir);
+    }
+
+    // Anything the checks above missed still has to load in this app
+    let has_errors = diagnostics
+        .found
+        .iter()
+        .any(|d| d.severity == DiagnosticSeverity::Error);
+    if !has_errors {
+        if let Err(e) = serde_json::from_value::<ClaudeSettings>(value) {
+            diagnostics.found.push(SettingsDiagnostic {
+                severity: DiagnosticSeverity::Error,
+                code: SettingsDiagnosticCode::InvalidType,
+                key: None,
+                message: e.to_string(),
+                line: None,
+                column: None,
+            });
+        }
+    }
+
+    let mut found = diagnostics.found;
+    found.sort_by_key(|d| (d.line.unwrap_or(usize::MAX), d.column));
+    found
+}
+
+fn check_permissions(
+    diagnostics: &mut Diagnostics,
+    permissions: &serde_json::Map<String, serde_json::Value>,
+) {
+    diagnostics.check_keys("permissions", permissions, PERMISSION_KEYS);
+
+    if let Some(mode) = permissions.get("defaultMode").and_then(|m| m.as_str()) {
+        if !PERMISSION_MODES.contains(&mode) {
+            diagnostics.error(
+                SettingsDiagnosticCode::InvalidValue,
+                "permissions.defaultMode",
+                format!(
+                    "Unknown permission mode `{mode}`; expected one of {}",
+                    PERMISSION_MODES.join(", ")
+                ),
+            );
+        }
+    }
+
+    for list in ["allow", "deny", "ask"] {
+        let Some(rules) = permissions.get(list).and_then(|l| l.as_array()) else {
+            continue;
+        };
+        for (index, rule) in rules.iter().enumerate() {
+            let Some(rule) = rule.as_str() else {
+                continue;
+            };
+            if PermissionRule::parse(rule).is_none() {
+                diagnostics.warning(
+                    SettingsDiagnosticCode::InvalidPermissionRule,
+                    &format!("permissions.{list}[{index}]"),
+                    format!("`{rule}` is not a valid permission rule"),
+                );
+            }
+        }
+    }
+}
+
+fn check_hooks(
+    diagnostics: &mut Diagnostics,
+    hooks: &serde_json::Map<String, serde_json::Value>,
+    path_var: Option<&OsStr>,
+    project_dir: Option<&Path>,
+) {
+    for (event, matchers) in hooks {
+        let event_key = child_key("hooks", event);
+        if !HOOK_EVENTS.contains(&event.as_str()) {
+            diagnostics.warning(
+                SettingsDiagnosticCode::UnknownKey,
+                &event_key,
+                format!("Unknown hook event `{event}`"),
+            );
+        }
+        let Some(matchers) = matchers.as_array() else {
+            diagnostics.error(
+                SettingsDiagnosticCode::InvalidType,
+                &event_key,
+                format!("`{event_key}` must be an array of matchers"),
+            );
+            continue;
+        };
+
+        for (index, matcher) in matchers.iter().enumerate() {
+            let matcher_key = format!("{event_key}[{index}]");
+            if matcher.get("matcher").is_some_and(|m| !m.is_string()) {
+                diagnostics.error(
+                    SettingsDiagnosticCode::InvalidType,
+                    &format!("{matcher_key}.matcher"),
+                    format!("`{matcher_key}.matcher` must be a string"),
+                );
+            }
+            let Some(commands) = matcher.get("hooks").and_then(|h| h.as_array()) else {
+                diagnostics.error(
+                    SettingsDiagnosticCode::InvalidType,
+                    &matcher_key,
+                    format!("`{matcher_key}` needs a `hooks` array"),
+                );
+                continue;
+            };
+
+            for (index, hook) in commands.iter().enumerate() {
+                let hook_key = format!("{matcher_key}.hooks[{index}]");
+                if hook.get("timeout").is_some_and(|t| !t.is_u64()) {
+                    diagnostics.error(
+                        SettingsDiagnosticCode::InvalidType,
+                        &format!("{hook_key}.timeout"),
+                        format!("`{hook_key}.timeout` must be a whole number of seconds"),
+                    );
+                }
+                if hook.get("type").and_then(|t| t.as_str()) != Some("command") {
+                    continue;
+                }
+                match hook.get("command").and_then(|c| c.as_str()) {
+                    Some(command) => {
+                        if let Some(program) = missing_program(command, path_var, project_dir) {
+                            diagnostics.warning(
+                                SettingsDiagnosticCode::HookCommandNotFound,
+                                &format!("{hook_key}.command"),
+                                format!("`{program}` was not found on PATH"),
+                            );
+                        }
+                    }
+                    None => diagnostics.error(
+                        SettingsDiagnosticCode::InvalidType,
+                        &hook_key,
+                        format!("`{hook_key}` needs a `command` string"),
+                    ),
+                }
+            }
+        }
+    }
+}
+
+/// The program a hook command starts, if it cannot be found
+///
+/// Commands that start with a variable, like `$CLAUDE_PROJECT_DIR/hook.sh`,
+/// are only resolved when the hook runs, so they are not checked; neither are
+/// relative paths without a `project_dir` to resolve them against.
+fn missing_program(
+    command: &str,
+    path_var: Option<&OsStr>,
+    project_dir: Option<&Path>,
+) -> Option<String> {
+    let program = shell_words(command)
+        .into_iter()
+        .find(|word| !is_env_assignment(word))?;
+    if program.contains('$') || SHELL_BUILTINS.contains(&program.as_str()) {
+        return None;
+    }
+
+    let found = if let Some(home_relative) = program.strip_prefix("~/") {
+        dirs::home_dir().is_some_and(|home| home.join(home_relative).is_file())
+    } else if Path::new(&program).is_absolute() {
+        Path::new(&program).is_file()
+    } else if program.contains(['/', '\\']) {
+        match project_dir {
+            Some(project_dir) => project_dir.join(&program).is_file(),
+            None => return None,
+        }
+    } else {
+        find_on_path(&program, path_var).is_some()
+    };
+    (!found).then_some(program)
+}
+
+/// Where `program` resolves on a `PATH`-style list of directories
+fn find_on_path(program: &str, path_var: Option<&OsStr>) -> Option<PathBuf> {
+    let extensions: Vec<String> = if cfg!(windows) {
+        std::env::var("PATHEXT")
+            .unwrap_or_else(|_| ".COM;.EXE;.BAT;.CMD".to_string())
+            .split(';')
+            .map(str::to_string)
+            .chain(std::iter::once(String::new()))
+            .collect()
+    } else {
+        vec![String::new()]
+    };
+
+    std::env::split_paths(path_var?).find_map(|dir| {
+        extensions
+            .iter()
+            .map(|extension| dir.join(format!("{program}{extension}")))
+            .find(|candidate| candidate.is_file())
+    })
+}
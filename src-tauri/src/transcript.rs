@@ -0,0 +1,658 @@
+use crate::models::{ClaudeMessage, ContentBlock, ExportOptions, ImageSource, MessageContent};
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use std::collections::HashSet;
+use std::fmt::Write;
+
+/// Input keys that best describe a tool call in its one-line summary, in order
+const SUMMARY_KEYS: &[&str] = &[
+    "description",
+    "file_path",
+    "pattern",
+    "command",
+    "url",
+    "query",
+];
+
+/// Characters of a tool call's summary line
+const SUMMARY_CHARS: usize = 80;
+
+const TIMESTAMP_FORMAT: &str = "%Y-%m-%d %H:%M:%S UTC";
+
+const HTML_STYLE: &str = r#"
+body { font-family: -apple-system, BlinkMacSystemFont, "Segoe UI", sans-serif; max-width: 960px; margin: 2rem auto; padding: 0 1rem; color: #1f2328; line-height: 1.5; }
+header { border-bottom: 1px solid #d0d7de; margin-bottom: 1.5rem; }
+dl { display: grid; grid-template-columns: max-content 1fr; gap: 0.25rem 1rem; }
+dt { font-weight: 600; }
+dd { margin: 0; }
+section { border: 1px solid #d0d7de; border-radius: 6px; padding: 0.75rem 1rem; margin-bottom: 1rem; }
+section.user { background: #f6f8fa; }
+section.summary { font-style: italic; }
+h2 { font-size: 1rem; margin: 0 0 0.5rem; }
+h2 time { font-weight: normal; color: #656d76; margin-left: 0.5rem; }
+p { white-space: pre-wrap; margin: 0.5rem 0; }
+pre { background: #f6f8fa; border: 1px solid #d0d7de; border-radius: 6px; padding: 0.75rem; overflow-x: auto; }
+details { margin: 0.5rem 0; }
+summary { cursor: pointer; font-family: ui-monospace, monospace; font-size: 0.875rem; }
+details.thinking { color: #656d76; }
+.result.error pre { border-color: #cf222e; }
+img { max-width: 100%; }
+"#;
+
+/// A piece of a turn, shared by the Markdown and HTML renderers
+enum Part<'a> {
+    Text(&'a str),
+    Tool {
+        name: &'a str,
+        input: &'a Value,
+        result: Option<(&'a [ContentBlock], bool)>,
+    },
+    /// A tool result whose tool call is not in the transcript
+    ToolResult {
+        content: &'a [ContentBlock],
+        is_error: bool,
+    },
+    Thinking(&'a str),
+    RedactedThinking,
+    Image {
+        media_type: &'a str,
+        source: &'a ImageSource,
+    },
+}
+
+struct Turn<'a> {
+    role: &'static str,
+    timestamp: Option<DateTime<Utc>>,
+    parts: Vec<Part<'a>>,
+}
+
+/// Session facts shown above the transcript
+struct Header {
+    session_id: String,
+    project_path: Option<String>,
+    branches: Vec<String>,
+    models: Vec<String>,
+    started: Option<DateTime<Utc>>,
+    ended: Option<DateTime<Utc>>,
+    message_count: usize,
+}
+
+impl Header {
+    fn new(session_id: &str, messages: &[ClaudeMessage]) -> Self {
+        let mut header = Header {
+            session_id: session_id.to_string(),
+            project_path: None,
+            branches: Vec::new(),
+            models: Vec::new(),
+            started: None,
+            ended: None,
+            message_count: 0,
+        };
+        for message in messages {
+            let (timestamp, cwd, git_branch, model) = match message {
+                ClaudeMessage::User {
+                    timestamp,
+                    cwd,
+                    git_branch,
+                    ..
+                } => (timestamp, cwd, git_branch, &None),
+                ClaudeMessage::Assistant {
+                    timestamp,
+                    cwd,
+                    git_branch,
+                    model,
+                    ..
+                } => (timestamp, cwd, git_branch, model),
+                ClaudeMessage::Summary { .. } => continue,
+            };
+            header.message_count += 1;
+            header.started = Some(header.started.map_or(*timestamp, |s| s.min(*timestamp)));
+            header.ended = Some(header.ended.map_or(*timestamp, |e| e.max(*timestamp)));
+            if header.project_path.is_none() && !cwd.is_empty() {
+                header.project_path = Some(cwd.clone());
+            }
+            for (value, seen) in [
+                (git_branch, &mut header.branches),
+                (model, &mut header.models),
+            ] {
+                if let Some(value) = value.as_ref().filter(|v| !v.is_empty()) {
+                    if !seen.contains(value) {
+                        seen.push(value.clone());
+                    }
+                }
+            }
+        }
+        header
+    }
+
+    fn rows(&self) -> Vec<(&'static str, String)> {
+        let mut rows = vec![("Session", self.session_id.clone())];
+        if let Some(project_path) = &self.project_path {
+            rows.push(("Project", project_path.clone()));
+        }
+        if !self.branches.is_empty() {
+            rows.push(("Branch", self.branches.join(", ")));
+        }
+        if !self.models.is_empty() {
+            rows.push(("Model", self.models.join(", ")));
+        }
+        if let Some(started) = self.started {
+            rows.push(("Started", started.format(TIMESTAMP_FORMAT).to_string()));
+        }
+        if let Some(ended) = self.ended {
+            rows.push(("Ended", ended.format(TIMESTAMP_FORMAT).to_string()));
+        }
+        rows.push(("Messages", self.message_count.to_string()));
+        rows
+    }
+
+    fn title(&self) -> String {
+        let project = self
+            .project_path
+            .as_deref()
+            .and_then(|path| path.rsplit(['/', '\\']).find(|part| !part.is_empty()));
+        let short_id: String = self.session_id.chars().take(8).collect();
+        match project {
+            Some(project) => format!("{project} — session {short_id}"),
+            None => format!("Session {short_id}"),
+        }
+    }
+}
+
+/// Readable Markdown transcript of a session
+///
+/// Tool calls are wrapped in `<details>` so they collapse on GitHub and in
+/// most Markdown viewers.
+pub fn render_markdown(
+    session_id: &str,
+    messages: &[ClaudeMessage],
+    options: &ExportOptions,
+) -> String {
+    let header = Header::new(session_id, messages);
+    let mut out = format!("# {}\n\n", header.title());
+    for (label, value) in header.rows() {
+        let _ = writeln!(out, "- **{label}:** {value}");
+    }
+
+    for turn in turns(messages, options) {
+        if turn.role == "Summary" {
+            for part in &turn.parts {
+                if let Part::Text(text) = part {
+                    let _ = write!(out, "\n> **Summary:** {}\n", text.replace('\n', "\n> "));
+                }
+            }
+            continue;
+        }
+
+        match turn.timestamp {
+            Some(timestamp) => {
+                let _ = write!(
+                    out,
+                    "\n## {} · {}\n",
+                    turn.role,
+                    timestamp.format(TIMESTAMP_FORMAT)
+                );
+            }
+            None => {
+                let _ = write!(out, "\n## {}\n", turn.role);
+            }
+        }
+        for part in &turn.parts {
+            out.push('\n');
+            match part {
+                Part::Text(text) => {
+                    out.push_str(text.trim_end());
+                    out.push('\n');
+                }
+                Part::Tool {
+                    name,
+                    input,
+                    result,
+                } => {
+                    let _ = write!(
+                        out,
+                        "<details>\n<summary>{}</summary>\n\n",
+                        html_escape(&tool_summary(name, input))
+                    );
+                    let (language, code) = tool_input_code(input);
+                    out.push_str(&markdown_fence(&code, language));
+                    if let Some((content, is_error)) = result {
+                        let label = if *is_error { "Error" } else { "Result" };
+                        let _ = write!(out, "\n**{label}:**\n\n");
+                        out.push_str(&markdown_fence(
+                            &result_text(content),
+                            result_language(input),
+                        ));
+                    }
+                    out.push_str("\n</details>\n");
+                }
+                Part::ToolResult { content, is_error } => {
+                    let label = if *is_error {
+                        "Tool error"
+                    } else {
+                        "Tool result"
+                    };
+                    let _ = write!(out, "<details>\n<summary>{label}</summary>\n\n");
+                    out.push_str(&markdown_fence(&result_text(content), "text"));
+                    out.push_str("\n</details>\n");
+                }
+                Part::Thinking(thinking) => {
+                    out.push_str("<details>\n<summary>Thinking</summary>\n\n");
+                    out.push_str(thinking.trim_end());
+                    out.push_str("\n\n</details>\n");
+                }
+                Part::RedactedThinking => out.push_str("_Thinking redacted_\n"),
+                Part::Image { media_type, source } => match source {
+                    ImageSource::Url { url } => {
+                        let _ = writeln!(out, "![{media_type}]({url})");
+                    }
+                    _ => {
+                        let _ = writeln!(out, "_[{media_type} image]_");
+                    }
+                },
+            }
+        }
+    }
+    out
+}
+
+/// Single-file HTML page of a session, with its CSS embedded
+pub fn render_html(
+    session_id: &str,
+    messages: &[ClaudeMessage],
+    options: &ExportOptions,
+) -> String {
+    let header = Header::new(session_id, messages);
+    let title = html_escape(&header.title());
+    let mut out = format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n<style>{HTML_STYLE}</style>\n</head>\n<body>\n<header>\n<h1>{title}</h1>\n<dl>\n"
+    );
+    for (label, value) in header.rows() {
+        let _ = writeln!(out, "<dt>{label}</dt><dd>{}</dd>", html_escape(&value));
+    }
+    out.push_str("</dl>\n</header>\n<main>\n");
+
+    for turn in turns(messages, options) {
+        let _ = write!(
+            out,
+            "<section class=\"{}\">\n<h2>{}",
+            turn.role.to_lowercase(),
+            turn.role
+        );
+        if let Some(timestamp) = turn.timestamp {
+            let _ = write!(
+                out,
+                "<time datetime=\"{}\">{}</time>",
+                timestamp.to_rfc3339(),
+                timestamp.format(TIMESTAMP_FORMAT)
+            );
+        }
+        out.push_str("</h2>\n");
+
+        for part in &turn.parts {
+            match part {
+                Part::Text(text) => out.push_str(&html_text(text)),
+                Part::Tool {
+                    name,
+                    input,
+                    result,
+                } => {
+                    let _ = write!(
+                        out,
+                        "<details class=\"tool\">\n<summary>{}</summary>\n",
+                        html_escape(&tool_summary(name, input))
+                    );
+                    let (language, code) = tool_input_code(input);
+                    out.push_str(&html_code(&code, language));
+                    if let Some((content, is_error)) = result {
+                        let (class, label) = if *is_error {
+                            ("result error", "Error")
+                        } else {
+                            ("result", "Result")
+                        };
+                        let _ = write!(out, "<div class=\"{class}\">\n<strong>{label}</strong>\n");
+                        out.push_str(&html_code(&result_text(content), result_language(input)));
+                        out.push_str("</div>\n");
+                    }
+                    out.push_str("</details>\n");
+                }
+                Part::ToolResult { content, is_error } => {
+                    let label = if *is_error {
+                        "Tool error"
+                    } else {
+                        "Tool result"
+                    };
+                    let _ = write!(
+                        out,
+                        "<details class=\"tool\">\n<summary>{label}</summary>\n"
+                    );
+                    out.push_str(&html_code(&result_text(content), "text"));
+                    out.push_str("</details>\n");
+                }
+                Part::Thinking(thinking) => {
+                    out.push_str("<details class=\"thinking\">\n<summary>Thinking</summary>\n");
+                    out.push_str(&html_text(thinking));
+                    out.push_str("</details>\n");
+                }
+                Part::RedactedThinking => out.push_str("<p><em>Thinking redacted</em></p>\n"),
+                Part::Image { media_type, source } => match source {
+                    ImageSource::Base64 { data } => {
+                        let _ = writeln!(
+                            out,
+                            "<img src=\"data:{};base64,{}\" alt=\"{}\">",
+                            html_escape(media_type),
+                            html_escape(data),
+                            html_escape(media_type)
+                        );
+                    }
+                    ImageSource::Url { url } => {
+                        let _ = writeln!(
+                            out,
+                            "<img src=\"{}\" alt=\"{}\">",
+                            html_escape(url),
+                            html_escape(media_type)
+                        );
+                    }
+                    ImageSource::Handle { .. } => {
+                        let _ =
+                            writeln!(out, "<p><em>[{} image]</em></p>", html_escape(media_type));
+                    }
+                },
+            }
+        }
+        out.push_str("</section>\n");
+    }
+    out.push_str("</main>\n</body>\n</html>\n");
+    out
+}
+
+/// The turns of a transcript in reading order
+///
+/// Tool results are shown under the tool call they answer, so user messages
+/// that only carry paired results are left out.
+fn turns<'a>(messages: &'a [ClaudeMessage], options: &ExportOptions) -> Vec<Turn<'a>> {
+    let paired: HashSet<&str> = messages
+        .iter()
+        .filter_map(|message| match message {
+            ClaudeMessage::Assistant {
+                content: MessageContent::Assistant { content, .. },
+                ..
+            } => Some(content),
+            _ => None,
+        })
+        .flatten()
+        .filter_map(|block| match block {
+            ContentBlock::ToolUse {
+                id,
+                result: Some(_),
+                ..
+            } => Some(id.as_str()),
+            _ => None,
+        })
+        .collect();
+
+    let mut turns = Vec::new();
+    for message in messages {
+        let (role, timestamp, content) = match message {
+            ClaudeMessage::User {
+                timestamp, content, ..
+            } => ("User", *timestamp, content),
+            ClaudeMessage::Assistant {
+                timestamp, content, ..
+            } => ("Assistant", *timestamp, content),
+            ClaudeMessage::Summary { summary, .. } => {
+                turns.push(Turn {
+                    role: "Summary",
+                    timestamp: None,
+                    parts: vec![Part::Text(summary)],
+                });
+                continue;
+            }
+        };
+
+        let parts: Vec<Part> = match content {
+            MessageContent::User { content, .. } => {
+                if content.trim().is_empty() {
+                    Vec::new()
+                } else {
+                    vec![Part::Text(content)]
+                }
+            }
+            MessageContent::UserBlocks { content, .. }
+            | MessageContent::Assistant { content, .. } => content
+                .iter()
+                .filter_map(|block| block_part(block, &paired, options))
+                .collect(),
+        };
+        if !parts.is_empty() {
+            turns.push(Turn {
+                role,
+                timestamp: Some(timestamp),
+                parts,
+            });
+        }
+    }
+    turns
+}
+
+fn block_part<'a>(
+    block: &'a ContentBlock,
+    paired: &HashSet<&str>,
+    options: &ExportOptions,
+) -> Option<Part<'a>> {
+    match block {
+        ContentBlock::Text { text } if !text.trim().is_empty() => Some(Part::Text(text)),
+        ContentBlock::Text { .. } => None,
+        ContentBlock::ToolUse {
+            name,
+            input,
+            result,
+            ..
+        } => Some(Part::Tool {
+            name,
+            input,
+            result: result
+                .as_ref()
+                .map(|result| (result.content.as_slice(), result.is_error)),
+        }),
+        ContentBlock::ToolResult { tool_use_id, .. } if paired.contains(tool_use_id.as_str()) => {
+            None
+        }
+        ContentBlock::ToolResult {
+            content, is_error, ..
+        } => Some(Part::ToolResult {
+            content,
+            is_error: *is_error,
+        }),
+        ContentBlock::Thinking { thinking, .. } if options.include_thinking => {
+            Some(Part::Thinking(thinking))
+        }
+        ContentBlock::RedactedThinking { .. } if options.include_thinking => {
+            Some(Part::RedactedThinking)
+        }
+        ContentBlock::Thinking { .. } | ContentBlock::RedactedThinking { .. } => None,
+        ContentBlock::Image { media_type, source } => Some(Part::Image { media_type, source }),
+    }
+}
+
+/// One line naming the tool and what it was called on, e.g. `Bash: Run the tests`
+fn tool_summary(name: &str, input: &Value) -> String {
+    let detail = SUMMARY_KEYS
+        .iter()
+        .find_map(|key| input.get(key).and_then(|v| v.as_str()))
+        .and_then(|detail| detail.lines().next())
+        .filter(|detail| !detail.trim().is_empty());
+    match detail {
+        Some(detail) if detail.chars().count() > SUMMARY_CHARS => {
+            let short: String = detail.chars().take(SUMMARY_CHARS).collect();
+            format!("{name}: {short}…")
+        }
+        Some(detail) => format!("{name}: {detail}"),
+        None => name.to_string(),
+    }
+}
+
+/// The code block that shows a tool call's input, and its language
+fn tool_input_code(input: &Value) -> (&'static str, String) {
+    let field = |key: &str| input.get(key).and_then(|v| v.as_str());
+    if let Some(command) = field("command") {
+        return ("bash", command.to_string());
+    }
+    if let (Some(old), Some(new)) = (field("old_string"), field("new_string")) {
+        let mut diff = String::new();
+        for line in old.lines() {
+            let _ = writeln!(diff, "-{line}");
+        }
+        for line in new.lines() {
+            let _ = writeln!(diff, "+{line}");
+        }
+        return ("diff", diff);
+    }
+    if let (Some(path), Some(content)) = (field("file_path"), field("content")) {
+        return (language_for_path(path), content.to_string());
+    }
+    (
+        "json",
+        serde_json::to_string_pretty(input).unwrap_or_default(),
+    )
+}
+
+/// Results of tools that read a file are highlighted as that file's language
+fn result_language(input: &Value) -> &'static str {
+    input
+        .get("file_path")
+        .and_then(|v| v.as_str())
+        .map(language_for_path)
+        .unwrap_or("text")
+}
+
+fn result_text(content: &[ContentBlock]) -> String {
+    content
+        .iter()
+        .map(|block| match block {
+            ContentBlock::Text { text } => text.clone(),
+            ContentBlock::Image { media_type, .. } => format!("[{media_type} image]"),
+            _ => String::new(),
+        })
+        .filter(|text| !text.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Fence name of the language a file is written in, from its extension
+fn language_for_path(path: &str) -> &'static str {
+    let extension = path
+        .rsplit_once('.')
+        .map(|(_, extension)| extension.to_ascii_lowercase())
+        .unwrap_or_default();
+    match extension.as_str() {
+        "rs" => "rust",
+        "ts" | "mts" | "cts" => "typescript",
+        "tsx" => "tsx",
+        "js" | "mjs" | "cjs" => "javascript",
+        "jsx" => "jsx",
+        "py" => "python",
+        "go" => "go",
+        "rb" => "ruby",
+        "java" => "java",
+        "kt" | "kts" => "kotlin",
+        "swift" => "swift",
+        "c" | "h" => "c",
+        "cc" | "cpp" | "cxx" | "hpp" => "cpp",
+        "cs" => "csharp",
+        "sh" | "bash" | "zsh" => "bash",
+        "json" | "jsonl" => "json",
+        "toml" => "toml",
+        "yaml" | "yml" => "yaml",
+        "md" | "markdown" => "markdown",
+        "html" | "htm" => "html",
+        "css" | "scss" => "css",
+        "sql" => "sql",
+        "xml" => "xml",
+        _ => "text",
+    }
+}
+
+/// A fenced code block that cannot be closed early by backticks in `code`
+fn markdown_fence(code: &str, language: &str) -> String {
+    let mut longest = 0;
+    let mut run = 0;
+    for c in code.chars() {
+        run = if c == '`' { run + 1 } else { 0 };
+        longest = longest.max(run);
+    }
+    let fence = "`".repeat((longest + 1).max(3));
+    format!(
+        "{fence}{language}\n{}\n{fence}\n",
+        code.trim_end_matches('\n')
+    )
+}
+
+fn html_code(code: &str, language: &str) -> String {
+    format!(
+        "<pre><code class=\"language-{}\">{}</code></pre>\n",
+        html_escape(language),
+        html_escape(code.trim_end_matches('\n'))
+    )
+}
+
+/// Paragraphs of Markdown text, with its fenced code blocks kept as code
+fn html_text(text: &str) -> String {
+    let mut out = String::new();
+    let mut paragraph: Vec<&str> = Vec::new();
+    let mut code: Option<(String, &str, Vec<&str>)> = None;
+
+    let flush = |paragraph: &mut Vec<&str>, out: &mut String| {
+        if !paragraph.is_empty() {
+            let _ = writeln!(out, "<p>{}</p>", html_escape(&paragraph.join("\n")));
+            paragraph.clear();
+        }
+    };
+
+    for line in text.lines() {
+        let trimmed = line.trim_start();
+        if let Some((fence, language, lines)) = &mut code {
+            if trimmed.starts_with(fence.as_str())
+                && trimmed.trim_start_matches('`').trim().is_empty()
+            {
+                out.push_str(&html_code(&lines.join("\n"), language));
+                code = None;
+            } else {
+                lines.push(line);
+            }
+        } else if trimmed.starts_with("```") {
+            flush(&mut paragraph, &mut out);
+            let fence_len = trimmed.chars().take_while(|c| *c == '`').count();
+            let language = trimmed[fence_len..]
+                .split_whitespace()
+                .next()
+                .unwrap_or("text");
+            code = Some(("`".repeat(fence_len), language, Vec::new()));
+        } else if line.trim().is_empty() {
+            flush(&mut paragraph, &mut out);
+        } else {
+            paragraph.push(line);
+        }
+    }
+    // An unclosed fence runs to the end of the text
+    if let Some((_, language, lines)) = code {
+        out.push_str(&html_code(&lines.join("\n"), language));
+    }
+    flush(&mut paragraph, &mut out);
+    out
+}
+
+fn html_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
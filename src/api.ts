@@ -2,16 +2,46 @@ import type {
   ClaudeSession,
   ClaudeMessage,
   CommandLogEntry,
+  CommandAnalytics,
   TodoItem,
   ClaudeSettings,
   ProjectSummary,
   SessionStats,
+  SessionTree,
+  SessionDiagnostics,
+  CostReport,
+  PriceTable,
   IdeInfo,
   ClaudeDirectoryInfo,
   CustomCommand,
   Agent,
+  FileChangeEvent,
+  MessageSearchHit,
+  PermissionSuggestion,
+  PermissionSimulation,
+  HookTestRequest,
+  HookTestResult,
+  EffectiveSettings,
+  SettingsDiagnostic,
+  SettingsSaveResult,
+  FileBackup,
+  ExportOptions,
+  SessionExport,
+  RedactionReport,
+  ManagerError,
 } from "./types";
 
+// Tauri event names emitted by the backend file watcher
+const FILE_CHANGE_EVENTS = [
+  "session-updated",
+  "todo-changed",
+  "settings-changed",
+  "command-changed",
+  "agent-changed",
+  "ide-lock-added",
+  "ide-lock-removed",
+];
+
 // Check if we're running in Tauri environment
 // Tauri 2.0 では __TAURI__ の代わりに直接 API のインポートを試行
 let isTauri = false;
@@ -33,6 +63,34 @@ try {
   mockApi = mockModule.mockApi;
 }
 
+// Whether a rejected command failed with the backend's structured error
+export function isManagerError(err: unknown): err is ManagerError {
+  return (
+    typeof err === "object" &&
+    err !== null &&
+    "code" in err &&
+    "message" in err
+  );
+}
+
+// Message of any rejection, whether from the backend or the mock API
+export function errorMessage(err: unknown, fallback: string): string {
+  if (isManagerError(err) || err instanceof Error) {
+    return err.message;
+  }
+  return typeof err === "string" ? err : fallback;
+}
+
+// One line per blocking problem, for errors thrown from a refused save
+export function formatSettingsDiagnostics(
+  diagnostics: SettingsDiagnostic[],
+): string {
+  return diagnostics
+    .filter((d) => d.severity === "error")
+    .map((d) => (d.line ? `${d.line}:${d.column}: ${d.message}` : d.message))
+    .join("\n");
+}
+
 export const api = {
   // Session management
   async getAllSessions(): Promise<ClaudeSession[]> {
@@ -57,6 +115,63 @@ export const api = {
     return mockApi.getSessionMessages(sessionId);
   },
 
+  async getSessionTree(sessionId: string): Promise<SessionTree> {
+    if (isTauri && tauriApi) {
+      return tauriApi.invoke("get_session_tree", { sessionId });
+    }
+    // Mock sessions are linear, so expose them as a single thread
+    const messages = await mockApi.getSessionMessages(sessionId);
+    const nodes = messages.map((message: ClaudeMessage, index: number) => ({
+      uuid: "uuid" in message ? message.uuid : `summary-${index}`,
+      parent_uuid: "parent_uuid" in message ? message.parent_uuid : undefined,
+      children: [],
+      is_sidechain: false,
+      is_compact_boundary: false,
+      on_active_path: true,
+      message,
+    }));
+    return {
+      session_id: sessionId,
+      nodes,
+      roots: nodes.length > 0 ? [nodes[0].uuid] : [],
+      active_leaf: nodes[nodes.length - 1]?.uuid,
+      active_path: nodes.map((node: { uuid: string }) => node.uuid),
+      sidechains: [],
+    };
+  },
+
+  async getSessionDiagnostics(sessionId: string): Promise<SessionDiagnostics> {
+    if (isTauri && tauriApi) {
+      return tauriApi.invoke("get_session_diagnostics", { sessionId });
+    }
+    // Mock sessions are generated, so every line parses
+    const messages = await mockApi.getSessionMessages(sessionId);
+    return {
+      session_id: sessionId,
+      file_path: `${sessionId}.jsonl`,
+      line_count: messages.length,
+      health: "healthy",
+      lines: [],
+      unknown_types: {},
+    };
+  },
+
+  async getImageData(
+    sessionId: string,
+    messageUuid: string,
+    blockPath: number[],
+  ): Promise<string> {
+    if (isTauri && tauriApi) {
+      return tauriApi.invoke("get_image_data", {
+        sessionId,
+        messageUuid,
+        blockPath,
+      });
+    }
+    // Mock sessions never contain detached images
+    return "";
+  },
+
   async searchSessions(query: string): Promise<ClaudeSession[]> {
     if (isTauri && tauriApi) {
       return tauriApi.invoke("search_sessions", { query });
@@ -64,6 +179,17 @@ export const api = {
     return mockApi.searchSessions(query);
   },
 
+  async searchMessages(
+    query: string,
+    limit?: number,
+  ): Promise<MessageSearchHit[]> {
+    if (isTauri && tauriApi) {
+      return tauriApi.invoke("search_messages", { query, limit });
+    }
+    // Mock API has no full-text index
+    return [];
+  },
+
   // Command history
   async getCommandHistory(): Promise<CommandLogEntry[]> {
     if (isTauri && tauriApi) {
@@ -79,6 +205,19 @@ export const api = {
     return mockApi.searchCommands(query);
   },
 
+  async getCommandAnalytics(limit?: number): Promise<CommandAnalytics> {
+    if (isTauri && tauriApi) {
+      return tauriApi.invoke("get_command_analytics", { limit });
+    }
+    return {
+      total_commands: 0,
+      top_commands: [],
+      by_project: [],
+      by_day: [],
+      slowest: [],
+    };
+  },
+
   // Todo management
   async getTodos(): Promise<TodoItem[]> {
     if (isTauri && tauriApi) {
@@ -98,10 +237,14 @@ export const api = {
   async saveSettings(settings: ClaudeSettings): Promise<void> {
     if (isTauri && tauriApi) {
       const settingsJson = JSON.stringify(settings, null, 2);
-      return tauriApi.invoke("save_settings_file", {
-        filename: "settings.json",
-        content: settingsJson,
-      });
+      const result: SettingsSaveResult = await tauriApi.invoke(
+        "save_settings_file",
+        { filename: "settings.json", content: settingsJson },
+      );
+      if (!result.saved) {
+        throw new Error(formatSettingsDiagnostics(result.diagnostics));
+      }
+      return;
     }
     return mockApi.saveSettings(settings);
   },
@@ -121,12 +264,78 @@ export const api = {
     return mockApi.getSessionStats();
   },
 
+  // Cost estimation
+  async getCostReport(): Promise<CostReport> {
+    if (isTauri && tauriApi) {
+      return tauriApi.invoke("get_cost_report");
+    }
+    return {
+      currency: "USD",
+      total_usage: {
+        input_tokens: 0,
+        output_tokens: 0,
+        cache_creation_input_tokens: 0,
+        cache_read_input_tokens: 0,
+      },
+      total_cost: {
+        input: 0,
+        output: 0,
+        cache_write: 0,
+        cache_read: 0,
+        total: 0,
+      },
+      by_session: [],
+      by_project: [],
+      by_day: [],
+      by_model: [],
+      unpriced_models: [],
+    };
+  },
+
+  async getPriceTable(): Promise<PriceTable> {
+    if (isTauri && tauriApi) {
+      return tauriApi.invoke("get_price_table");
+    }
+    return { currency: "USD", models: [] };
+  },
+
+  async savePriceTable(priceTable: PriceTable): Promise<void> {
+    if (isTauri && tauriApi) {
+      return tauriApi.invoke("save_price_table", { priceTable });
+    }
+    return Promise.resolve();
+  },
+
   // Export functionality
-  async exportSessionData(sessionId: string): Promise<string> {
+  async exportSessionData(
+    sessionId: string,
+    options?: ExportOptions,
+  ): Promise<SessionExport> {
+    if (isTauri && tauriApi) {
+      return tauriApi.invoke("export_session_data", { sessionId, options });
+    }
+    // Mock API only renders JSON and doesn't redact
+    return {
+      content: await mockApi.exportSessionData(sessionId),
+      report: { redactions: [], counts: {} },
+    };
+  },
+
+  async exportSessionToFile(
+    sessionId: string,
+    options: ExportOptions,
+    path: string,
+    overwrite?: boolean,
+  ): Promise<RedactionReport> {
     if (isTauri && tauriApi) {
-      return tauriApi.invoke("export_session_data", { sessionId });
+      return tauriApi.invoke("export_session_to_file", {
+        sessionId,
+        options,
+        path,
+        overwrite,
+      });
     }
-    return mockApi.exportSessionData(sessionId);
+    return Promise.reject(new Error("Mock API can't write files"));
   },
 
   // IDE window activation
@@ -174,6 +383,26 @@ export const api = {
     return Promise.resolve();
   },
 
+  // Subscribe to debounced file changes; resolves to an unsubscribe function
+  async onFileChange(
+    handler: (change: FileChangeEvent) => void,
+  ): Promise<() => void> {
+    if (isTauri && tauriApi) {
+      const { listen } = await import("@tauri-apps/api/event");
+      const unlisteners = await Promise.all(
+        FILE_CHANGE_EVENTS.map((eventName) =>
+          listen<FileChangeEvent>(eventName, (event) =>
+            handler(event.payload),
+          ),
+        ),
+      );
+      await tauriApi.invoke("start_file_watcher");
+      return () => unlisteners.forEach((unlisten) => unlisten());
+    }
+    // Mock data never changes
+    return () => {};
+  },
+
   // .claude directory management
   async getClaudeDirectoryInfo(
     projectPath: string,
@@ -191,11 +420,45 @@ export const api = {
     return mockApi.readClaudeFile(filePath);
   },
 
-  async writeClaudeFile(filePath: string, content: string): Promise<void> {
+  // Resolves to the version of the written content. With `expectedVersion`,
+  // the write is rejected if the file changed since that version was read.
+  async writeClaudeFile(
+    filePath: string,
+    content: string,
+    expectedVersion?: string,
+  ): Promise<string> {
+    if (isTauri && tauriApi) {
+      return tauriApi.invoke("write_claude_file", {
+        filePath,
+        content,
+        expectedVersion,
+      });
+    }
+    await mockApi.writeClaudeFile(filePath, content);
+    return "";
+  },
+
+  async getFileVersion(filePath: string): Promise<string | null> {
+    if (isTauri && tauriApi) {
+      return tauriApi.invoke("get_file_version", { filePath });
+    }
+    // Mock API doesn't track versions
+    return null;
+  },
+
+  async listBackups(filePath: string): Promise<FileBackup[]> {
+    if (isTauri && tauriApi) {
+      return tauriApi.invoke("list_backups", { filePath });
+    }
+    // Mock API doesn't keep backups
+    return [];
+  },
+
+  async restoreBackup(filePath: string, backupId: string): Promise<string> {
     if (isTauri && tauriApi) {
-      return tauriApi.invoke("write_claude_file", { filePath, content });
+      return tauriApi.invoke("restore_backup", { filePath, backupId });
     }
-    return mockApi.writeClaudeFile(filePath, content);
+    return Promise.reject(new Error("Mock API doesn't keep backups"));
   },
 
   // Custom commands and agents
@@ -213,18 +476,28 @@ export const api = {
     return mockApi.getAgents();
   },
 
-  async saveCustomCommand(name: string, content: string): Promise<void> {
+  async saveCustomCommand(
+    name: string,
+    content: string,
+    expectedVersion?: string,
+  ): Promise<string> {
     if (isTauri && tauriApi) {
-      return tauriApi.invoke("save_custom_command", { name, content });
+      return tauriApi.invoke("save_custom_command", { name, content, expectedVersion });
     }
-    return mockApi.saveCustomCommand(name, content);
+    await mockApi.saveCustomCommand(name, content);
+    return "";
   },
 
-  async saveAgent(name: string, content: string): Promise<void> {
+  async saveAgent(
+    name: string,
+    content: string,
+    expectedVersion?: string,
+  ): Promise<string> {
     if (isTauri && tauriApi) {
-      return tauriApi.invoke("save_agent", { name, content });
+      return tauriApi.invoke("save_agent", { name, content, expectedVersion });
     }
-    return mockApi.saveAgent(name, content);
+    await mockApi.saveAgent(name, content);
+    return "";
   },
 
   async deleteCustomCommand(name: string): Promise<void> {
@@ -263,11 +536,100 @@ export const api = {
     return mockApi.getAllSettingsFiles();
   },
 
-  async saveSettingsFile(filename: string, content: string): Promise<void> {
+  async saveSettingsFile(
+    filename: string,
+    content: string,
+    force?: boolean,
+    expectedVersion?: string,
+  ): Promise<SettingsSaveResult> {
+    if (isTauri && tauriApi) {
+      return tauriApi.invoke("save_settings_file", {
+        filename,
+        content,
+        force,
+        expectedVersion,
+      });
+    }
+    await mockApi.saveSettingsFile(filename, content);
+    return { saved: true, diagnostics: [], version: null };
+  },
+
+  async validateSettings(content: string): Promise<SettingsDiagnostic[]> {
+    if (isTauri && tauriApi) {
+      return tauriApi.invoke("validate_settings", { content });
+    }
+    // Mock API only checks the syntax
+    try {
+      JSON.parse(content);
+      return [];
+    } catch (err) {
+      return [
+        {
+          severity: "error",
+          code: "invalid_json",
+          key: null,
+          message: err instanceof Error ? err.message : String(err),
+          line: null,
+          column: null,
+        },
+      ];
+    }
+  },
+
+  async suggestPermissionRules(
+    limit?: number,
+  ): Promise<PermissionSuggestion[]> {
+    if (isTauri && tauriApi) {
+      return tauriApi.invoke("suggest_permission_rules", { limit });
+    }
+    // Mock API has no tool usage to learn from
+    return [];
+  },
+
+  async applyPermissionSuggestion(
+    filePath: string,
+    rule: string,
+  ): Promise<void> {
+    if (isTauri && tauriApi) {
+      return tauriApi.invoke("apply_permission_suggestion", { filePath, rule });
+    }
+    return Promise.resolve();
+  },
+
+  async simulatePermission(
+    toolName: string,
+    input: Record<string, unknown>,
+    cwd?: string,
+  ): Promise<PermissionSimulation> {
+    if (isTauri && tauriApi) {
+      return tauriApi.invoke("simulate_permission", { toolName, input, cwd });
+    }
+    return {
+      decision: "ask",
+      decided_by: null,
+      reason: "Mock API has no permission rules",
+      matched_rules: [],
+      invalid_rules: [],
+    };
+  },
+
+  async testHook(request: HookTestRequest): Promise<HookTestResult> {
+    if (isTauri && tauriApi) {
+      return tauriApi.invoke("test_hook", { request });
+    }
+    // Mock API doesn't run processes
+    return { payload: {}, matched: true, runs: [] };
+  },
+
+  async getEffectiveSettings(
+    projectPath?: string,
+  ): Promise<EffectiveSettings> {
     if (isTauri && tauriApi) {
-      return tauriApi.invoke("save_settings_file", { filename, content });
+      return tauriApi.invoke("get_effective_settings", { projectPath });
     }
-    return mockApi.saveSettingsFile(filename, content);
+    // Mock API only has user settings
+    const settings = await mockApi.getSettings();
+    return { settings, layers: [], origins: [], overridden: [] };
   },
 
   // Cache management
@@ -15,6 +15,7 @@ const CommandStatistics: React.FC<{ commands: CommandLogEntry[] }> = ({
     const totalCommands = commands.length;
     const uniqueUsers = new Set(commands.map((cmd) => cmd.user)).size;
     const recentCommands = commands.filter((cmd) => {
+      if (!cmd.timestamp) return false;
       const cmdDate = new Date(cmd.timestamp);
       const dayAgo = new Date(Date.now() - 24 * 60 * 60 * 1000);
       return cmdDate > dayAgo;
@@ -340,13 +341,19 @@ const EnhancedVirtualizedCommandList: React.FC<{
           <header className="command-header">
             <div className="command-meta">
               <span className="command-user">{cmd.user}</span>
-              <time
-                className="command-time"
-                dateTime={cmd.timestamp}
-                title={formatDateTooltip(cmd.timestamp)}
-              >
-                {formatDateTime(cmd.timestamp, { style: "technical" })}
-              </time>
+              {cmd.timestamp ? (
+                <time
+                  className="command-time"
+                  dateTime={cmd.timestamp}
+                  title={formatDateTooltip(cmd.timestamp)}
+                >
+                  {formatDateTime(cmd.timestamp, { style: "technical" })}
+                </time>
+              ) : (
+                <span className="command-time" title="Unrecognized timestamp">
+                  {cmd.raw_timestamp}
+                </span>
+              )}
             </div>
             <button
               className={`copy-button ${copyFeedback === index ? "copied" : ""}`}
@@ -1,6 +1,6 @@
 import React, { useEffect, useState, useRef, useCallback } from "react";
 import { marked } from "marked";
-import { api } from "../api";
+import { api, errorMessage, isManagerError } from "../api";
 import {
   normalizeProjectPathSync,
   getProjectDisplayName,
@@ -14,6 +14,9 @@ import type {
   ProjectSummary,
   ClaudeDirectoryInfo,
   ClaudeDirectoryFile,
+  SessionDiagnostics,
+  ExportFormat,
+  RedactionReport,
 } from "../types";
 
 interface ProjectScreenProps {
@@ -28,6 +31,8 @@ export const ProjectScreen: React.FC<ProjectScreenProps> = ({
     null,
   );
   const [messages, setMessages] = useState<ClaudeMessage[]>([]);
+  const [sessionDiagnostics, setSessionDiagnostics] =
+    useState<SessionDiagnostics | null>(null);
   const [projectSummary, setProjectSummary] = useState<ProjectSummary | null>(
     null,
   );
@@ -60,6 +65,11 @@ export const ProjectScreen: React.FC<ProjectScreenProps> = ({
   const [selectedMessageType, setSelectedMessageType] = useState<string>("all");
   const [filteredMessages, setFilteredMessages] = useState<ClaudeMessage[]>([]);
   const [renderAsMarkdown, setRenderAsMarkdown] = useState(false);
+  const [exportFormat, setExportFormat] = useState<ExportFormat>("markdown");
+  const [exportThinking, setExportThinking] = useState(false);
+  const [exportRedacted, setExportRedacted] = useState(true);
+  const [exportPath, setExportPath] = useState("");
+  const [isExporting, setIsExporting] = useState(false);
   const messageListRef = useRef<HTMLDivElement>(null);
   const [isRefreshing, setIsRefreshing] = useState(false);
 
@@ -464,6 +474,11 @@ export const ProjectScreen: React.FC<ProjectScreenProps> = ({
       const data = await api.getSessionMessages(session.session_id);
       setMessages(data);
       setFilteredMessages(data);
+      setSessionDiagnostics(
+        session.health && session.health !== "healthy"
+          ? await api.getSessionDiagnostics(session.session_id)
+          : null,
+      );
 
       if (data.length > 0 && session.latest_content_preview) {
         setTimeout(() => {
@@ -517,6 +532,54 @@ export const ProjectScreen: React.FC<ProjectScreenProps> = ({
     }
   };
 
+  const exportSession = async (session: ClaudeSession) => {
+    const extension = { json: "json", markdown: "md", html: "html" }[
+      exportFormat
+    ];
+    setIsExporting(true);
+    try {
+      const path =
+        exportPath.trim() ||
+        `${await api.getHomeDirectory()}/session-${session.session_id.substring(0, 8)}.${extension}`;
+      const options = {
+        format: exportFormat,
+        include_thinking: exportThinking,
+        redaction: exportRedacted ? {} : undefined,
+      };
+      let report: RedactionReport;
+      try {
+        report = await api.exportSessionToFile(
+          session.session_id,
+          options,
+          path,
+        );
+      } catch (err) {
+        if (!isManagerError(err) || err.code !== "already_exists") throw err;
+        if (!window.confirm(`${path} already exists. Overwrite it?`)) return;
+        report = await api.exportSessionToFile(
+          session.session_id,
+          options,
+          path,
+          true,
+        );
+      }
+      const redacted = report.redactions.length;
+      toast.success(
+        "Session exported",
+        redacted > 0 ? `${path} (${redacted} redacted)` : path,
+        3000,
+      );
+    } catch (err) {
+      toast.error(
+        "Export failed",
+        errorMessage(err, "Failed to export session"),
+        4000,
+      );
+    } finally {
+      setIsExporting(false);
+    }
+  };
+
   const getMessageTextContent = (message: ClaudeMessage): string => {
     if (message.message_type === "summary") {
       return message.summary;
@@ -847,6 +910,21 @@ export const ProjectScreen: React.FC<ProjectScreenProps> = ({
                           </span>
                           {session.is_processing ? "Processing" : "Complete"}
                         </span>
+                        {session.health && session.health !== "healthy" && (
+                          <span
+                            className={`session-health-badge health-${session.health}`}
+                            title={
+                              session.health === "corrupted"
+                                ? "Some lines of this session could not be read"
+                                : "This session has entries of an unknown type"
+                            }
+                          >
+                            ⚠️{" "}
+                            {session.health === "corrupted"
+                              ? "Corrupted"
+                              : "Unknown entries"}
+                          </span>
+                        )}
                       </div>
                     </div>
                     {session.latest_content_preview && (
@@ -949,6 +1027,52 @@ export const ProjectScreen: React.FC<ProjectScreenProps> = ({
                       </label>
                     </div>
 
+                    <div className="session-export">
+                      <select
+                        value={exportFormat}
+                        onChange={(e) =>
+                          setExportFormat(e.target.value as ExportFormat)
+                        }
+                        aria-label="Export format"
+                      >
+                        <option value="markdown">Markdown</option>
+                        <option value="html">HTML</option>
+                        <option value="json">JSON</option>
+                      </select>
+                      <label>
+                        <input
+                          type="checkbox"
+                          checked={exportThinking}
+                          onChange={(e) => setExportThinking(e.target.checked)}
+                        />
+                        Thinking
+                      </label>
+                      <label
+                        title="Replace secrets, emails, host names and the home directory"
+                      >
+                        <input
+                          type="checkbox"
+                          checked={exportRedacted}
+                          onChange={(e) => setExportRedacted(e.target.checked)}
+                        />
+                        Redact
+                      </label>
+                      <input
+                        type="text"
+                        value={exportPath}
+                        onChange={(e) => setExportPath(e.target.value)}
+                        placeholder="Export path (defaults to home directory)"
+                        aria-label="Export path"
+                      />
+                      <button
+                        onClick={() => exportSession(selectedSession)}
+                        disabled={isExporting}
+                        title="Export transcript"
+                      >
+                        {isExporting ? "Exporting..." : "Export"}
+                      </button>
+                    </div>
+
                     <button
                       className="refresh-messages-button"
                       onClick={() => loadSessionMessages(selectedSession)}
@@ -960,6 +1084,29 @@ export const ProjectScreen: React.FC<ProjectScreenProps> = ({
                   </div>
                 </div>
 
+                {sessionDiagnostics && sessionDiagnostics.lines.length > 0 && (
+                  <details className="session-diagnostics">
+                    <summary>
+                      {sessionDiagnostics.lines.length} of{" "}
+                      {sessionDiagnostics.line_count} lines were skipped or
+                      not recognized
+                    </summary>
+                    <ul>
+                      {sessionDiagnostics.lines.map((diagnostic) => (
+                        <li key={diagnostic.offset}>
+                          <span className="diagnostic-location">
+                            Line {diagnostic.line} (byte {diagnostic.offset})
+                          </span>{" "}
+                          {diagnostic.message}
+                          <code className="diagnostic-excerpt">
+                            {diagnostic.excerpt}
+                          </code>
+                        </li>
+                      ))}
+                    </ul>
+                  </details>
+                )}
+
                 {loadingMessages ? (
                   <div className="loading">Loading messages...</div>
                 ) : filteredMessages.length === 0 ? (
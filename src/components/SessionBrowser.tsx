@@ -348,6 +348,18 @@ export const SessionBrowser: React.FC<SessionBrowserProps> = ({
                     >
                       <span className="status-dot"></span>
                     </span>
+                    {session.health && session.health !== "healthy" && (
+                      <span
+                        className={`session-health-badge health-${session.health}`}
+                        title={
+                          session.health === "corrupted"
+                            ? "Some lines of this session could not be read"
+                            : "This session has entries of an unknown type"
+                        }
+                      >
+                        ⚠️
+                      </span>
+                    )}
                   </h4>
                   <div className="session-actions">
                     {session.ide_info && (
@@ -1,5 +1,5 @@
 import { useState, useEffect } from "react";
-import { api } from "../api";
+import { api, errorMessage, formatSettingsDiagnostics } from "../api";
 import JSONEditor from "./JSONEditor";
 import { SafeConfirmDialog } from "./SafeConfirmDialog";
 import { validateFileName } from "../utils/security";
@@ -31,9 +31,7 @@ export function SettingsFilesEditor() {
         setSelectedContent(files[0][1]);
       }
     } catch (err) {
-      setError(
-        err instanceof Error ? err.message : "Failed to load settings files",
-      );
+      setError(errorMessage(err, "Failed to load settings files"));
     } finally {
       setIsLoading(false);
     }
@@ -89,7 +87,11 @@ export function SettingsFilesEditor() {
 
     try {
       setIsSaving(true);
-      await api.saveSettingsFile(selectedFile, selectedContent);
+      const result = await api.saveSettingsFile(selectedFile, selectedContent);
+      if (!result.saved) {
+        setError(formatSettingsDiagnostics(result.diagnostics));
+        return;
+      }
 
       // Update the local state
       setSettingsFiles((prev) =>
@@ -100,9 +102,7 @@ export function SettingsFilesEditor() {
 
       setHasChanges(false);
     } catch (err) {
-      setError(
-        err instanceof Error ? err.message : "Failed to save settings file",
-      );
+      setError(errorMessage(err, "Failed to save settings file"));
     } finally {
       setIsSaving(false);
     }
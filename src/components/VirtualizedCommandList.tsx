@@ -116,14 +116,20 @@ const CommandItem: React.FC<CommandItemProps> = React.memo(
             <span className="command-user" aria-label="User">
               {cmd.user}
             </span>
-            <time
-              className="command-time"
-              dateTime={cmd.timestamp}
-              title={formatDateTooltip(cmd.timestamp)}
-              aria-label={`Executed at ${formatDateTooltip(cmd.timestamp)}`}
-            >
-              {formatDateTime(cmd.timestamp, { style: "technical" })}
-            </time>
+            {cmd.timestamp ? (
+              <time
+                className="command-time"
+                dateTime={cmd.timestamp}
+                title={formatDateTooltip(cmd.timestamp)}
+                aria-label={`Executed at ${formatDateTooltip(cmd.timestamp)}`}
+              >
+                {formatDateTime(cmd.timestamp, { style: "technical" })}
+              </time>
+            ) : (
+              <span className="command-time" title="Unrecognized timestamp">
+                {cmd.raw_timestamp}
+              </span>
+            )}
           </div>
           <div className="command-actions">
             {isLong && (
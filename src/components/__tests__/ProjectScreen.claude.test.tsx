@@ -193,7 +193,7 @@ describe("ProjectScreen - .claude Directory Tab", () => {
 
     vi.mocked(api.getClaudeDirectoryInfo).mockResolvedValue(mockDirectoryInfo);
     vi.mocked(api.readClaudeFile).mockResolvedValue(originalContent);
-    vi.mocked(api.writeClaudeFile).mockResolvedValue(undefined);
+    vi.mocked(api.writeClaudeFile).mockResolvedValue("1f2e3d4c5b6a7980-22");
 
     render(<ProjectScreen projectPath={mockProjectPath} />);
 
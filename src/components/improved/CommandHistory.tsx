@@ -113,13 +113,19 @@ const CommandCard = React.memo<CommandCardProps>(
       >
         <header className="command-card-header">
           <div className="command-meta">
-            <time
-              className="command-timestamp"
-              dateTime={command.timestamp}
-              title={formatDateTooltip(command.timestamp)}
-            >
-              {formatDateTime(command.timestamp)}
-            </time>
+            {command.timestamp ? (
+              <time
+                className="command-timestamp"
+                dateTime={command.timestamp}
+                title={formatDateTooltip(command.timestamp)}
+              >
+                {formatDateTime(command.timestamp)}
+              </time>
+            ) : (
+              <span className="command-timestamp" title="Unrecognized timestamp">
+                {command.raw_timestamp}
+              </span>
+            )}
             <span
               className="command-user"
               aria-label={`Executed by ${command.user}`}
@@ -11,6 +11,8 @@ interface ImprovedPermissionsTabProps {
   loading: boolean;
   error: string | null;
   onRetry: () => void;
+  /** Create a settings file when none exists yet */
+  onCreate?: () => void;
 }
 
 /**
@@ -36,7 +38,7 @@ interface ImprovedPermissionsTabProps {
  * ```
  */
 export const ImprovedPermissionsTab: FC<ImprovedPermissionsTabProps> =
-  React.memo(({ settings, loading, error, onRetry }) => {
+  React.memo(({ settings, loading, error, onRetry, onCreate }) => {
     const [showDeveloperMode, setShowDeveloperMode] = useState(false);
     const [isEditing, setIsEditing] = useState(false);
     const [editedSettings, setEditedSettings] = useState<ClaudeSettings | null>(
@@ -185,6 +187,15 @@ export const ImprovedPermissionsTab: FC<ImprovedPermissionsTabProps> =
             <p className="settings-empty-state__description">
               No permissions or hooks configuration could be found.
             </p>
+            {onCreate && (
+              <button
+                type="button"
+                onClick={onCreate}
+                className="settings-button settings-button--primary"
+              >
+                Create settings.json
+              </button>
+            )}
             <button
               type="button"
               onClick={onRetry}
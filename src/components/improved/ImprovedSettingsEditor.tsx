@@ -5,7 +5,7 @@ import React, {
   useState,
   useMemo,
 } from "react";
-import { api } from "../../api";
+import { api, errorMessage, isManagerError } from "../../api";
 import type { ClaudeSettings } from "../../types";
 import { SettingsCard } from "../ui/SettingsCard";
 import { SettingsTabContainer, type Tab } from "../ui/SettingsTabNavigation";
@@ -44,13 +44,27 @@ export const ImprovedSettingsEditor: FC = React.memo(() => {
       const data = await api.getSettings();
       setSettings(data);
     } catch (err) {
+      if (isManagerError(err) && err.code === "not_found") {
+        // No settings.json yet; the empty state offers to create one
+        setSettings(null);
+        return;
+      }
       console.error("Failed to load settings:", err);
-      setError(err instanceof Error ? err.message : "Failed to load settings");
+      setError(errorMessage(err, "Failed to load settings"));
     } finally {
       setLoading(false);
     }
   }, []);
 
+  const createSettings = useCallback(async () => {
+    try {
+      await api.saveSettingsFile("settings.json", "{}\n");
+      await loadSettings();
+    } catch (err) {
+      setError(errorMessage(err, "Failed to create settings.json"));
+    }
+  }, [loadSettings]);
+
   useEffect(() => {
     loadSettings();
   }, [loadSettings]);
@@ -120,6 +134,7 @@ export const ImprovedSettingsEditor: FC = React.memo(() => {
               loading={loading}
               error={error}
               onRetry={loadSettings}
+              onCreate={createSettings}
             />
           );
 
@@ -145,7 +160,7 @@ export const ImprovedSettingsEditor: FC = React.memo(() => {
           );
       }
     },
-    [settings, loading, error, loadSettings],
+    [settings, loading, error, loadSettings, createSettings],
   );
 
   const handleTabChange = useCallback((tabId: string) => {
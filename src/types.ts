@@ -17,6 +17,17 @@ export interface ClaudeSession {
   ide_info?: IdeInfo;
   is_processing: boolean;
   file_modified_time: string;
+  token_usage?: TokenUsage;
+  health?: SessionHealth;
+}
+
+export type SessionHealth = "healthy" | "unrecognized" | "corrupted";
+
+export interface TokenUsage {
+  input_tokens: number;
+  output_tokens: number;
+  cache_creation_input_tokens: number;
+  cache_read_input_tokens: number;
 }
 
 export type ProcessingStatus = "processing" | "completed" | "stopped" | "error";
@@ -44,6 +55,9 @@ export type ClaudeMessage =
       git_branch?: string;
       processing_status: ProcessingStatus;
       stop_reason?: string;
+      model?: string;
+      usage?: TokenUsage;
+      request_id?: string;
     }
   | {
       message_type: "summary";
@@ -57,7 +71,38 @@ export type MessageContent =
 
 export type ContentBlock =
   | { type: "text"; text: string }
-  | { type: "tool_use"; id: string; name: string; input: any };
+  | {
+      type: "tool_use";
+      id: string;
+      name: string;
+      input: any;
+      result?: ToolResult;
+    }
+  | {
+      type: "tool_result";
+      tool_use_id: string;
+      content: ContentBlock[];
+      is_error: boolean;
+    }
+  | { type: "thinking"; thinking: string; signature?: string }
+  | { type: "redacted_thinking"; data: string }
+  | { type: "image"; media_type: string; source: ImageSource };
+
+export type ImageSource =
+  | { type: "base64"; data: string }
+  | { type: "url"; url: string }
+  | {
+      type: "handle";
+      message_uuid: string;
+      block_path: number[];
+      size: number;
+    };
+
+export interface ToolResult {
+  content: ContentBlock[];
+  is_error: boolean;
+  message_uuid: string;
+}
 
 export interface TodoItem {
   id: string;
@@ -67,10 +112,50 @@ export interface TodoItem {
 }
 
 export interface CommandLogEntry {
-  timestamp: string;
+  /** null when raw_timestamp could not be parsed */
+  timestamp: string | null;
+  raw_timestamp?: string;
   user: string;
   command: string;
   cwd?: string;
+  source?: CommandSource;
+  session_id?: string;
+  git_branch?: string;
+  description?: string;
+  status?: CommandStatus;
+  exit_code?: number;
+  /** Time from the Bash call to its result, when both are known */
+  duration_ms?: number;
+}
+
+export type CommandSource = "history_log" | "session" | "both";
+
+export type CommandStatus = "succeeded" | "failed" | "interrupted" | "pending";
+
+export interface CommandFrequency {
+  /** Program and subcommand, e.g. "cargo test" */
+  command: string;
+  count: number;
+  /** Runs that finished with a known result */
+  completed: number;
+  failures: number;
+  /** failures / completed, null when no run has a result */
+  failure_rate: number | null;
+  last_run: string | null;
+}
+
+export interface CommandCount {
+  key: string;
+  count: number;
+}
+
+export interface CommandAnalytics {
+  total_commands: number;
+  top_commands: CommandFrequency[];
+  by_project: CommandCount[];
+  /** Local calendar days (YYYY-MM-DD), oldest first */
+  by_day: CommandCount[];
+  slowest: CommandLogEntry[];
 }
 
 export interface ClaudeSettings {
@@ -82,13 +167,63 @@ export interface PermissionSettings {
   defaultMode: string;
   allow: string[];
   deny: string[];
+  /** Calls matching these always prompt, even when an allow rule matches */
+  ask?: string[];
+}
+
+export type PermissionDecision = "allow" | "deny" | "ask";
+
+export interface SourcedPermissionRule {
+  rule: string;
+  list: PermissionDecision;
+  /** Settings file the rule comes from */
+  source: string;
+}
+
+export interface PermissionSimulation {
+  decision: PermissionDecision;
+  /** null when the permission mode decided */
+  decided_by: SourcedPermissionRule | null;
+  reason: string;
+  matched_rules: SourcedPermissionRule[];
+  invalid_rules: SourcedPermissionRule[];
+}
+
+export interface ToolCallPreview {
+  session_id: string;
+  tool_name: string;
+  /** The command, file or URL the call worked on */
+  summary: string;
+  cwd: string | null;
+  timestamp: string | null;
+}
+
+export interface PermissionSuggestion {
+  /** Rule in settings syntax, e.g. "Bash(npm test:*)" */
+  rule: string;
+  tool_name: string;
+  /** Approved calls the rule would have matched */
+  count: number;
+  session_count: number;
+  last_used: string | null;
+  /** Most recent matching calls */
+  matches: ToolCallPreview[];
 }
 
 export interface HookSettings {
   PreToolUse: HookMatcher[];
+  PostToolUse?: HookMatcher[];
+  UserPromptSubmit?: HookMatcher[];
+  Notification?: HookMatcher[];
+  Stop?: HookMatcher[];
+  SubagentStop?: HookMatcher[];
+  PreCompact?: HookMatcher[];
+  SessionStart?: HookMatcher[];
+  SessionEnd?: HookMatcher[];
 }
 
 export interface HookMatcher {
+  /** Events that do not involve a tool, such as Stop, have no matcher */
   matcher: string;
   hooks: Hook[];
 }
@@ -96,6 +231,191 @@ export interface HookMatcher {
 export interface Hook {
   type: string;
   command: string;
+  /** Seconds before Claude Code stops the hook */
+  timeout?: number;
+}
+
+/**
+ * Hooks to run against a sample event. The tool call can be given directly or
+ * replayed from a session through session_id and tool_use_id.
+ */
+export interface HookTestRequest {
+  event: string;
+  matcher?: string;
+  hooks: Hook[];
+  tool_name?: string;
+  tool_input?: unknown;
+  tool_response?: unknown;
+  /** Prompt for UserPromptSubmit, message for Notification */
+  prompt?: string;
+  session_id?: string;
+  tool_use_id?: string;
+  cwd?: string;
+}
+
+export type HookVerdict =
+  | "proceed"
+  | "approve"
+  | "ask"
+  | "block"
+  | "add_context"
+  | "stop_session"
+  | "non_blocking_error"
+  | "skipped";
+
+export interface HookRunResult {
+  command: string;
+  /** null when the hook timed out or was killed by a signal */
+  exit_code: number | null;
+  stdout: string;
+  stderr: string;
+  duration_ms: number;
+  timed_out: boolean;
+  json_output: unknown | null;
+  verdict: HookVerdict;
+  reason: string | null;
+  additional_context: string | null;
+}
+
+export interface HookTestResult {
+  /** The JSON sent to each hook on stdin */
+  payload: unknown;
+  /** false when the matcher does not select the tool, so no hook ran */
+  matched: boolean;
+  runs: HookRunResult[];
+}
+
+/** Settings files from the highest precedence down */
+export type SettingsScope = "enterprise" | "project_local" | "project" | "user";
+
+export interface SettingsLayer {
+  scope: SettingsScope;
+  path: string;
+  exists: boolean;
+  /** Why an existing file was skipped */
+  error: string | null;
+}
+
+export interface SettingOrigin {
+  /** Dotted key such as `permissions.allow` or `env.RUST_LOG` */
+  key: string;
+  value: unknown;
+  scope: SettingsScope;
+  path: string;
+}
+
+export interface EffectiveSettings {
+  settings: ClaudeSettings;
+  layers: SettingsLayer[];
+  origins: SettingOrigin[];
+  /** Values hidden by the same key in a layer with higher precedence */
+  overridden: SettingOrigin[];
+}
+
+export type DiagnosticSeverity = "error" | "warning";
+
+export type SettingsDiagnosticCode =
+  | "invalid_json"
+  | "invalid_type"
+  | "invalid_value"
+  | "unknown_key"
+  | "deprecated_key"
+  | "invalid_permission_rule"
+  | "hook_command_not_found";
+
+export interface SettingsDiagnostic {
+  severity: DiagnosticSeverity;
+  code: SettingsDiagnosticCode;
+  /** Dotted key the problem is about, e.g. `permissions.allow[2]` */
+  key: string | null;
+  message: string;
+  /** 1-based position in the file */
+  line: number | null;
+  column: number | null;
+}
+
+export interface SettingsSaveResult {
+  /** False when errors were found and the save was not forced */
+  saved: boolean;
+  diagnostics: SettingsDiagnostic[];
+  /** Version of the content on disk after a save, for the next `expectedVersion` */
+  version: string | null;
+}
+
+export type ExportFormat = "json" | "markdown" | "html";
+
+export interface ExportOptions {
+  format: ExportFormat;
+  /** Include the model's thinking blocks */
+  include_thinking?: boolean;
+  /** Omit to export the messages as they are */
+  redaction?: RedactionOptions;
+}
+
+/** Omitted flags default to true */
+export interface RedactionOptions {
+  secrets?: boolean;
+  home_directory?: boolean;
+  emails?: boolean;
+  hostnames?: boolean;
+  custom_patterns?: string[];
+}
+
+export type RedactionKind =
+  | "secret"
+  | "home_directory"
+  | "email"
+  | "hostname"
+  | "custom";
+
+export interface Redaction {
+  kind: RedactionKind;
+  rule: string;
+  message_index: number;
+  message_uuid?: string;
+  /** Dotted path within the message, e.g. `content[1].input.command` */
+  field: string;
+  replacement: string;
+}
+
+export interface RedactionReport {
+  redactions: Redaction[];
+  counts: Record<string, number>;
+}
+
+export interface SessionExport {
+  content: string;
+  report: RedactionReport;
+}
+
+export interface FileBackup {
+  /** Timestamp id passed to `restoreBackup` */
+  id: string;
+  path: string;
+  created_at: string;
+  size: number;
+}
+
+export type ManagerErrorCode =
+  | "not_found"
+  | "permission_denied"
+  | "already_exists"
+  | "parse"
+  | "validation"
+  | "conflict"
+  | "unsupported"
+  | "io"
+  | "internal";
+
+/** Error every backend command rejects with */
+export interface ManagerError {
+  code: ManagerErrorCode;
+  message: string;
+  /** File the error is about */
+  path: string | null;
+  session_id: string | null;
+  /** 1-based line in `path` */
+  line: number | null;
 }
 
 export interface ProjectSummary {
@@ -138,3 +458,120 @@ export interface Agent {
   name: string;
   content: string;
 }
+
+export interface ModelPrice {
+  model_pattern: string;
+  input_per_mtok: number;
+  output_per_mtok: number;
+  cache_write_per_mtok: number;
+  cache_read_per_mtok: number;
+}
+
+export interface PriceTable {
+  currency: string;
+  models: ModelPrice[];
+}
+
+export interface CostBreakdown {
+  input: number;
+  output: number;
+  cache_write: number;
+  cache_read: number;
+  total: number;
+}
+
+export interface CostEntry {
+  key: string;
+  usage: TokenUsage;
+  cost: CostBreakdown;
+}
+
+export interface CostReport {
+  currency: string;
+  total_usage: TokenUsage;
+  total_cost: CostBreakdown;
+  by_session: CostEntry[];
+  by_project: CostEntry[];
+  by_day: CostEntry[];
+  by_model: CostEntry[];
+  unpriced_models: string[];
+}
+
+export interface TreeNode {
+  uuid: string;
+  parent_uuid?: string;
+  children: string[];
+  is_sidechain: boolean;
+  is_compact_boundary: boolean;
+  on_active_path: boolean;
+  message?: ClaudeMessage;
+}
+
+export interface SidechainThread {
+  root_uuid: string;
+  parent_uuid?: string;
+  tool_use_id?: string;
+  message_uuids: string[];
+}
+
+export interface SessionTree {
+  session_id: string;
+  nodes: TreeNode[];
+  roots: string[];
+  active_leaf?: string;
+  active_path: string[];
+  sidechains: SidechainThread[];
+}
+
+export type SessionLineIssue =
+  | "invalid_json"
+  | "not_an_object"
+  | "missing_type"
+  | "unknown_type"
+  | "truncated";
+
+export interface SessionLineDiagnostic {
+  line: number;
+  offset: number;
+  issue: SessionLineIssue;
+  message: string;
+  excerpt: string;
+}
+
+export interface SessionDiagnostics {
+  session_id: string;
+  file_path: string;
+  line_count: number;
+  health: SessionHealth;
+  lines: SessionLineDiagnostic[];
+  unknown_types: Record<string, number>;
+}
+
+export type FileChangeKind =
+  | "session_updated"
+  | "todo_changed"
+  | "settings_changed"
+  | "command_changed"
+  | "agent_changed"
+  | "ide_lock_added"
+  | "ide_lock_removed";
+
+export interface FileChangeEvent {
+  kind: FileChangeKind;
+  ids: string[];
+}
+
+export interface SnippetSegment {
+  text: string;
+  highlighted: boolean;
+}
+
+export interface MessageSearchHit {
+  session_id: string;
+  project_path: string;
+  message_uuid: string;
+  message_type: "user" | "assistant";
+  timestamp: string;
+  score: number;
+  snippet: SnippetSegment[];
+}